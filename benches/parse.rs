@@ -2,7 +2,28 @@
 extern crate criterion;
 extern crate eterm_parser;
 
-use criterion::{black_box, Criterion};
+use criterion::{black_box, Criterion, Throughput};
+
+/// The screens parsed in a batch, the regexes compiled once are shared by all of them.
+const BATCH: usize = 100;
+
+/// Bench the parser on one screen, and on a batch of screens such as a session replies.
+/// Compare with the baseline of the previous version by
+/// `cargo bench -- --save-baseline before` then `cargo bench -- --baseline before`.
+fn bench_parse(c: &mut Criterion, name: &str, text: &str, parse: impl Fn(&str)) {
+    c.bench_function(name, |b| b.iter(|| parse(black_box(text))));
+
+    let mut group = c.benchmark_group(format!("{name}_batch"));
+    group.throughput(Throughput::Elements(BATCH as u64));
+    group.bench_function(format!("{BATCH}"), |b| {
+        b.iter(|| {
+            for _ in 0..BATCH {
+                parse(black_box(text));
+            }
+        })
+    });
+    group.finish();
+}
 
 fn parse_av_benchmarks(c: &mut Criterion) {
     let text = r" 03AUG(THU) PKXSHA VIA KN  
@@ -23,8 +44,8 @@ fn parse_av_benchmarks(c: &mut Criterion) {
    *MU8610  DS# J7 C7 D7 Q6 I4 YA BA MA EA HA     PVG 1545   1650   73E 0^   E  
 >   FM9530      KA LA NA RA SA VA TA GS ZA                          T2 T1 07:35";
 
-    c.bench_function("parse_av", |b| {
-        b.iter(|| eterm_parser::parse_av(black_box(text)).unwrap())
+    bench_parse(c, "parse_av", text, |text| {
+        black_box(eterm_parser::parse_av(text).unwrap());
     });
 }
 
@@ -44,8 +65,8 @@ TAX:               EXEMPTCN|OI:
 TAX:            CNY 60.00YQ|                                                   +
 ";
 
-    c.bench_function("parse_detr", |b| {
-        b.iter(|| eterm_parser::parse_detr(black_box(text)).unwrap())
+    bench_parse(c, "parse_detr", text, |text| {
+        black_box(eterm_parser::parse_detr(text).unwrap());
     });
 }
 
@@ -69,8 +90,8 @@ fn parse_fd_benchmarks(c: &mut Criterion) {
                                                                                 
 PAGE 1/1       /LPRIC/C52DZF3YARTGI11                                           ";
 
-    c.bench_function("parse_fd", |b| {
-        b.iter(|| eterm_parser::parse_fd(black_box(text)).unwrap())
+    bench_parse(c, "parse_fd", text, |text| {
+        black_box(eterm_parser::parse_fd(text).unwrap());
     });
 }
 
@@ -86,8 +107,8 @@ URCNGQ
 NIL                                                                             
 TOTAL NUMBER    1";
 
-    c.bench_function("parse_ml", |b| {
-        b.iter(|| eterm_parser::parse_ml(black_box(text)).unwrap())
+    bench_parse(c, "parse_ml", text, |text| {
+        black_box(eterm_parser::parse_ml(text).unwrap());
     });
 }
 
@@ -97,8 +118,8 @@ fn parse_pat_benchmarks(c: &mut Criterion) {
 SFC:01   SFN:01                                                               
 PAGE 1/1       /LPRIC/L3OF13GAATTP15";
 
-    c.bench_function("parse_pat", |b| {
-        b.iter(|| eterm_parser::parse_pat(black_box(text)).unwrap())
+    bench_parse(c, "parse_pat", text, |text| {
+        black_box(eterm_parser::parse_pat(text).unwrap());
     });
 }
 
@@ -116,8 +137,8 @@ fn parse_pnr_benchmarks(c: &mut Criterion) {
 10.OSI JD CTCM15718791505/P1                                                    
 11.OSI JD ADT/8989198306575    ";
 
-    c.bench_function("parse_pnr", |b| {
-        b.iter(|| eterm_parser::parse_pnr(black_box(text)).unwrap())
+    bench_parse(c, "parse_pnr", text, |text| {
+        black_box(eterm_parser::parse_pnr(text).unwrap());
     });
 }
 
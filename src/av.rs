@@ -1,18 +1,303 @@
+use crate::error;
+use crate::layout::{Column, Layout, Row, RowWriter};
+use crate::lexer::{self, AvKind};
+use crate::normalize::{self, Line};
+use crate::profile::Profile;
+use crate::render::Render;
+use std::iter::Peekable;
+
+/// The columns of the line of flight, such as
+/// `1- *KN6856  DS# JA C8 YA BA HA KA LA RQ SQ TQ  PKXXIY 0900   1120   321 0^B  E  `,
+/// the union flight has the same columns without dpt, the index is read by the lexer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FlightColumns {
+    share: Column,
+    flight_no: Column,
+    status: Column,
+    cabins: Column,
+    dpt: Column,
+    arr: Column,
+    take_off: Column,
+    landing: Column,
+    addday: Column,
+    model: Column,
+    stops: Column,
+    asr: Column,
+    meal: Column,
+    eticket: Column,
+    price: Column,
+}
+
+const FLIGHT_LAYOUT: Layout<FlightColumns> = Layout {
+    name: "av flight",
+    version: 1,
+    columns: FlightColumns {
+        share: Column::new("share", 3, 4),
+        flight_no: Column::new("flight_no", 4, 11),
+        status: Column::new("status", 12, 15),
+        cabins: Column::new("cabins", 15, 47),
+        dpt: Column::new("dpt", 47, 50),
+        arr: Column::new("arr", 50, 53),
+        take_off: Column::new("take_off", 54, 58),
+        landing: Column::new("landing", 61, 65),
+        addday: Column::new("addday", 65, 68),
+        model: Column::new("model", 68, 71),
+        stops: Column::new("stops", 72, 73),
+        asr: Column::new("asr", 73, 74),
+        meal: Column::new("meal", 74, 75),
+        eticket: Column::new("eticket", 77, 78),
+        price: Column::rest("price", 78),
+    },
+};
+
+/// The columns of the `>` line of flight, such as
+/// `>   MU2104      GQ UQ ZQ                                            -- T3 02:20`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ContinuationColumns {
+    real_flight_no: Column,
+    cabins: Column,
+    dpt_terminal: Column,
+    arr_terminal: Column,
+    duration: Column,
+}
+
+const CONTINUATION_LAYOUT: Layout<ContinuationColumns> = Layout {
+    name: "av continuation",
+    version: 1,
+    columns: ContinuationColumns {
+        real_flight_no: Column::new("real_flight_no", 4, 11),
+        cabins: Column::new("cabins", 15, 61),
+        dpt_terminal: Column::new("dpt_terminal", 68, 70),
+        arr_terminal: Column::new("arr_terminal", 71, 73),
+        duration: Column::rest("duration", 74),
+    },
+};
+
+/// The columns of the line of sub cabins, such as `               **M1A Q1S`.
+const SUB_CABIN_LAYOUT: Layout<Column> = Layout {
+    name: "av sub cabin",
+    version: 1,
+    columns: Column::rest("cabins", 15),
+};
+
 /// The result that av text parsed.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct Av<'a> {
     pub dpt: Option<&'a str>,
     pub arr: Option<&'a str>,
     pub date: Option<&'a str>,
     pub query: Option<&'a str>,
+    /// the structured query that parsed from the header and the command echoed.
+    pub av_query: Option<AvQuery<'a>>,
     pub flights: Vec<AvFlight<'a>>,
     pub raw_text: &'a str,
+    /// the variant of the av that detected from the command and the columns.
+    pub variant: AvVariant,
+    /// the option is marked with `+`, there are more options on the next page.
+    pub has_next_page: bool,
+    /// the option is marked with `-`, there are options on the previous page.
+    pub has_prev_page: bool,
+}
+
+/// The variant of av display.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvVariant {
+    /// the domestic av, such as `AV:PEKSHA/03AUG`.
+    #[default]
+    Domestic,
+    /// the international av, such as `AVH/PEKLHR/15SEP`, which columns differ from the domestic av.
+    International,
+    /// the av that shows the lowest price of every option.
+    Priced,
+}
+
+impl AvVariant {
+    /// Detect the variant from the text of av.
+    pub fn detect(text: &str) -> Self {
+        Self::detect_with(text, Profile::detect(text))
+    }
+
+    fn detect_with(text: &str, profile: &Profile) -> Self {
+        let mut variant = Self::Domestic;
+        for line in normalize::lines(text) {
+            let is_prompt = line.is_prompt();
+            let line = line.text;
+            if is_prompt {
+                if line.starts_with("AVH") {
+                    variant = Self::International;
+                }
+            } else if lexer::option_index(line).is_some() {
+                let row = FLIGHT_LAYOUT.row_at(line, profile.av_offset);
+                if Av::has_domestic_columns(row) {
+                    if Av::parse_price(row.raw(&FLIGHT_LAYOUT.columns.price)).is_some() {
+                        return Self::Priced;
+                    }
+                } else {
+                    variant = Self::International;
+                    let mut flight = AvFlight::default();
+                    if Av::parse_flight_tokens(line, &mut flight).is_ok()
+                        && flight.lowest_price.is_some()
+                    {
+                        return Self::Priced;
+                    }
+                }
+            }
+        }
+        variant
+    }
+}
+
+/// The query of an Av, which is parsed from the header such as ` 03AUG(THU) PKXSHA VIA KN`
+/// and the command echoed such as `>AV:PKXSHA/03AUG/0800/KN/D`.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AvQuery<'a> {
+    pub date: &'a str,
+    pub weekday: Option<&'a str>,
+    pub dpt: &'a str,
+    pub arr: &'a str,
+    /// the carrier after VIA in the header or in the command, such as KN.
+    pub via_carrier: Option<&'a str>,
+    /// the city after VIA in the header, or the city between the city pair of the command.
+    pub via_point: Option<&'a str>,
+    /// the command asks for the direct flights only.
+    pub direct_only: bool,
+    /// the time that the command asks from, such as 0800.
+    pub time: Option<&'a str>,
+    /// the command echoed, such as `AV:PKXSHA/03AUG/KN`.
+    pub command: Option<&'a str>,
+}
+
+pub(crate) const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+impl<'a> AvQuery<'a> {
+    pub fn parse(text: &'a str) -> anyhow::Result<Self> {
+        let mut query = Self::default();
+        let mut header = false;
+        for line in normalize::lines(text) {
+            let line = line
+                .text
+                .trim_matches(|c: char| c.is_whitespace() || c == '\x1D');
+            if let Some(command) = line.strip_prefix("AV") {
+                query.command = Some(line);
+                Self::parse_command(command, &mut query);
+            } else if !header && lexer::header_date(line).is_some() {
+                header = true;
+                Self::parse_header(line, &mut query)?;
+            }
+        }
+        if !header {
+            return Err(anyhow::Error::msg("av header isn't found.".to_owned()));
+        }
+        Ok(query)
+    }
+
+    /// Parse the header such as `03AUG(THU) PKXSHA VIA KN`.
+    fn parse_header(line: &'a str, query: &mut Self) -> anyhow::Result<()> {
+        let mut tokens = line
+            .split(|c: char| c.is_whitespace() || c == '\x1D')
+            .filter(|x| !x.is_empty());
+        let (date, weekday) = match tokens.next().and_then(|x| x.split_once('(')) {
+            Some((date, weekday)) => (date, weekday.trim_end_matches(')')),
+            None => return Err(anyhow::Error::msg("av header has no date.".to_owned())),
+        };
+        query.date = date;
+        query.weekday = Some(weekday).filter(|x| !x.is_empty());
+        match tokens.next() {
+            Some(pair) if pair.len() == 6 && pair.is_ascii() => {
+                query.dpt = &pair[..3];
+                query.arr = &pair[3..];
+            }
+            _ => return Err(anyhow::Error::msg("av header has no city pair.".to_owned())),
+        }
+        while let Some(token) = tokens.next() {
+            if token == "VIA" {
+                match tokens.next() {
+                    Some(x) if x.len() == 2 => query.via_carrier = Some(x),
+                    Some(x) => query.via_point = Some(x),
+                    None => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse the command such as `:PKXSHA/03AUG/0800/KN/D` that after `AV`.
+    fn parse_command(command: &'a str, query: &mut Self) {
+        let command = command.trim_start_matches([':', ' ']);
+        // the international av, such as AVH/PEKLHR/15SEP.
+        let command = command.strip_prefix("H/").unwrap_or(command);
+        for (i, part) in command.split('/').map(|x| x.trim()).enumerate() {
+            match part {
+                // the city pair with a via point, such as PEKCANSHA.
+                x if i == 0 && x.len() == 9 && x.bytes().all(|b| b.is_ascii_uppercase()) => {
+                    query.via_point = query.via_point.or(Some(&x[3..6]));
+                }
+                "D" => query.direct_only = true,
+                x if x.len() == 4 && x.bytes().all(|b| b.is_ascii_digit()) => query.time = Some(x),
+                x if i > 1 && x.len() == 2 && x.bytes().all(|b| b.is_ascii_alphanumeric()) => {
+                    query.via_carrier = query.via_carrier.or(Some(x));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Return whether the weekday matches the date.
+    /// The year of the date is used first, otherwise the year given.
+    /// Return None when the year or the weekday is unknown.
+    pub fn is_weekday_valid(&self, year: Option<i32>) -> Option<bool> {
+        let weekday = self.weekday?;
+        let day = self.date.get(0..2)?.parse::<u32>().ok()?;
+        let month = MONTHS
+            .iter()
+            .position(|x| Some(*x) == self.date.get(2..5))? as u32
+            + 1;
+        let year = match self.date.get(5..7) {
+            Some(yy) => 2000 + yy.parse::<i32>().ok()?,
+            None => year?,
+        };
+        Some(WEEKDAYS[day_of_week(year, month, day)] == weekday)
+    }
+
+    /// Return the key that identify the query exactly, such as `PKXSHA/03AUG/VIA KN`.
+    pub fn key(&self) -> String {
+        let mut key = format!("{}{}/{}", self.dpt, self.arr, self.date);
+        if let Some(x) = self.via_point {
+            key.push_str(&format!("/VIA {}", x));
+        }
+        if let Some(x) = self.via_carrier {
+            key.push_str(&format!("/VIA {}", x));
+        }
+        if let Some(x) = self.time {
+            key.push_str(&format!("/{}", x));
+        }
+        if self.direct_only {
+            key.push_str("/D");
+        }
+        key
+    }
+}
+
+/// Return the day of week of the date, 0 is sunday.
+pub(crate) fn day_of_week(year: i32, month: u32, day: u32) -> usize {
+    const OFFSETS: [i64; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let year = i64::from(year) - i64::from(month < 3);
+    let w =
+        year + year / 4 - year / 100 + year / 400 + OFFSETS[month as usize - 1] + i64::from(day);
+    w.rem_euclid(7) as usize
 }
 
 /// The flights of an Av.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct AvFlight<'a> {
     pub index: u8,
+    /// the date of the header that the option is under.
+    pub date: Option<&'a str>,
+    /// the weekday of the header that the option is under, such as THU of `03AUG(THU)`.
+    pub weekday: Option<&'a str>,
     pub is_share_flight: bool,
     pub flight_no: &'a str,
     pub real_flight_no: Option<&'a str>,
@@ -21,6 +306,8 @@ pub struct AvFlight<'a> {
     pub arr: &'a str,
     pub take_off: &'a str,
     pub landing: &'a str,
+    /// the days added to the landing time, such as 1 of `0105+1`.
+    pub landing_addday: Option<u8>,
     pub model: &'a str,
     pub dpt_terminal: Option<&'a str>,
     pub arr_terminal: Option<&'a str>,
@@ -33,44 +320,245 @@ pub struct AvFlight<'a> {
     pub is_marriage_flight: bool,
     pub union_flights: Vec<AvFlight<'a>>,
     pub asr: bool,
+    /// the lowest price of the option that the priced av shows.
+    pub lowest_price: Option<f32>,
+}
+
+impl<'a> AvFlight<'a> {
+    /// Return the airline code of the flight, such as MU of MU2104.
+    pub fn airline(&self) -> Option<&'a str> {
+        self.flight_no.get(0..2)
+    }
+
+    /// Return the airline code of the carrier that operates the flight, such as MU of
+    /// the code share KN6856 that MU2104 operates.
+    pub fn operating_airline(&self) -> Option<&'a str> {
+        self.real_flight_no
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .unwrap_or(self.flight_no)
+            .get(0..2)
+    }
+
+    /// Return the flight and its union flights in order.
+    pub fn legs(&self) -> impl Iterator<Item = &AvFlight<'a>> {
+        std::iter::once(self).chain(self.union_flights.iter())
+    }
+
+    /// Return the cabin by its name, such as `Y` or the sub cabin `M1`.
+    pub fn cabin(&self, name: &str) -> Option<&AvCabin<'a>> {
+        self.cabins.iter().find(|x| x.name == name)
+    }
+
+    /// Return the sub cabins of the parent cabin, such as M1 of M.
+    pub fn sub_cabins<'s>(&'s self, parent: &'s str) -> impl Iterator<Item = &'s AvCabin<'a>> {
+        self.cabins.iter().filter(move |x| x.parent == Some(parent))
+    }
+
+    /// Return the parent cabin of a sub cabin.
+    pub fn parent_cabin(&self, cabin: &AvCabin) -> Option<&AvCabin<'a>> {
+        cabin.parent.and_then(|x| self.cabin(x))
+    }
+
+    /// Return the service cabin of every cabin of the flight.
+    pub fn service_cabins<'m>(
+        &'m self,
+        map: &'m ServiceCabinMap,
+    ) -> impl Iterator<Item = (&'m AvCabin<'a>, ServiceCabin)> + 'm {
+        let airline = self.airline().unwrap_or_default();
+        self.cabins
+            .iter()
+            .map(move |x| (x, x.service_cabin(airline, map)))
+    }
 }
 
 /// The cabins of an AvFlight.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct AvCabin<'a> {
     pub name: &'a str,
     pub state: &'a str,
+    pub status: CabinStatus,
     pub is_sub_cabin: bool,
+    /// the name of the parent cabin of a sub cabin, such as M of M1.
+    pub parent: Option<&'a str>,
     pub raw_text: &'a str,
 }
 
+/// The inventory status of a cabin in av.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CabinStatus {
+    /// A, 9 or more seats are available.
+    Available,
+    /// 0-9, the number of seats available.
+    Seats(u8),
+    /// L, no seat, but waitlist is open.
+    Waitlist,
+    /// Q, seats need to request from the airline.
+    Request,
+    /// S, no seat because of the sales limit, only waitlist.
+    Restricted,
+    /// C, the cabin is closed.
+    Closed,
+    /// X, the cabin is cancelled.
+    Cancelled,
+    /// Z, the status is unknown, mostly for the airline that isn't hosted.
+    Unknown,
+    /// the status letter that specified by airline.
+    Other(char),
+    /// the status is missing.
+    #[default]
+    None,
+}
+
+impl CabinStatus {
+    /// Decode the status letter of a cabin.
+    pub fn parse(state: &str) -> Self {
+        let mut chars = state.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Self::from_char(c),
+            _ => Self::None,
+        }
+    }
+
+    pub fn from_char(c: char) -> Self {
+        match c {
+            'A' => Self::Available,
+            '0'..='9' => Self::Seats(c as u8 - b'0'),
+            'L' => Self::Waitlist,
+            'Q' => Self::Request,
+            'S' => Self::Restricted,
+            'C' => Self::Closed,
+            'X' => Self::Cancelled,
+            'Z' => Self::Unknown,
+            c => Self::Other(c),
+        }
+    }
+
+    /// Return the estimate of the seats available.
+    /// when it's 'A', return 9 which means 9 or more.
+    /// when the cabin can't be sold, return 0.
+    /// when it's unknown, return None.
+    pub fn seats(&self) -> Option<u8> {
+        match self {
+            Self::Available => Some(9),
+            Self::Seats(n) => Some(*n),
+            Self::Waitlist | Self::Restricted | Self::Closed | Self::Cancelled => Some(0),
+            Self::Request | Self::Unknown | Self::Other(_) | Self::None => None,
+        }
+    }
+
+    /// Return whether the seats can be booked directly.
+    pub fn is_available(&self) -> bool {
+        self.seats().is_some_and(|n| n > 0)
+    }
+}
+
+/// The service cabin that a booking class belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceCabin {
+    First,
+    Business,
+    PremiumEconomy,
+    Economy,
+}
+
+impl ServiceCabin {
+    /// Return the service cabin of a booking class by the common convention.
+    pub fn of(booking_class: &str) -> Self {
+        match booking_class.chars().next() {
+            Some('F') | Some('A') | Some('P') => Self::First,
+            Some('J') | Some('C') | Some('D') | Some('I') | Some('R') => Self::Business,
+            Some('W') => Self::PremiumEconomy,
+            _ => Self::Economy,
+        }
+    }
+}
+
+/// The mapping of booking classes to service cabins per airline,
+/// the booking class that isn't mapped follows `ServiceCabin::of`.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::av::{ServiceCabin, ServiceCabinMap};
+///
+/// let mut map = ServiceCabinMap::default();
+/// map.insert("KN", "WP", ServiceCabin::Economy);
+/// assert_eq!(map.service_cabin("KN", "W"), ServiceCabin::Economy);
+/// assert_eq!(map.service_cabin("MU", "W"), ServiceCabin::PremiumEconomy);
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct ServiceCabinMap {
+    airlines: std::collections::HashMap<String, std::collections::HashMap<char, ServiceCabin>>,
+}
+
+impl ServiceCabinMap {
+    /// Map every booking class in `booking_classes` of the airline to the service cabin.
+    pub fn insert(
+        &mut self,
+        airline: &str,
+        booking_classes: &str,
+        cabin: ServiceCabin,
+    ) -> &mut Self {
+        let classes = self.airlines.entry(airline.to_owned()).or_default();
+        for c in booking_classes.chars() {
+            classes.insert(c, cabin);
+        }
+        self
+    }
+
+    /// Return the service cabin of the booking class of the airline.
+    pub fn service_cabin(&self, airline: &str, booking_class: &str) -> ServiceCabin {
+        booking_class
+            .chars()
+            .next()
+            .and_then(|c| self.airlines.get(airline)?.get(&c).copied())
+            .unwrap_or_else(|| ServiceCabin::of(booking_class))
+    }
+}
+
 impl<'a> AvCabin<'a> {
+    /// Parse a cabin such as `YA` or `M1A`, the last char is the status of the cabin.
+    pub fn parse(text: &'a str, is_sub_cabin: bool) -> Option<Self> {
+        let pos = match text.as_bytes().last()? {
+            b if b.is_ascii() => text.len() - 1,
+            _ => text.char_indices().last()?.0,
+        };
+        if pos == 0 {
+            return None;
+        }
+        let name = &text[..pos];
+        let state = &text[pos..];
+        let status = match state.as_bytes() {
+            [b] => CabinStatus::from_char(char::from(*b)),
+            _ => CabinStatus::parse(state),
+        };
+        Some(Self {
+            name,
+            state,
+            status,
+            is_sub_cabin,
+            parent: if is_sub_cabin { name.get(0..1) } else { None },
+            raw_text: text,
+        })
+    }
+
     /// Return whether the seat of the cabin is number.
     /// such as 1-9.
     pub fn is_num_state(&self) -> bool {
-        match self.state.chars().next() {
-            Some(c) => c.is_ascii_digit(),
-            _ => false,
-        }
+        matches!(self.status, CabinStatus::Seats(_))
     }
 
     /// Return whether the seat of the cabin is available.
     /// such as 1-9,A.
     pub fn is_available(&self) -> bool {
-        match self.state.chars().next() {
-            Some('0') | Some('A') => false,
-            Some(c) => c.is_ascii_digit(),
-            _ => false,
-        }
+        self.status.is_available()
     }
 
     /// Return whether the seat of the cabin is locked.
     /// such as C,Q.
     pub fn is_locked(&self) -> bool {
-        match self.state {
-            "C" | "Q" => true,
-            _ => false,
-        }
+        matches!(self.status, CabinStatus::Closed | CabinStatus::Request)
     }
 
     /// Return whether the seat of the cabin is soldout.
@@ -83,16 +571,35 @@ impl<'a> AvCabin<'a> {
     /// when it's number, return the number.
     /// when it's 'A',return 9.
     pub fn seat_quantity(&self) -> Option<u8> {
-        match self.state.chars().next() {
-            Some('A') => Some(9),
-            Some(c) if c.is_ascii_digit() => c.to_digit(10).map_or(None, |x| Some(x as u8)),
+        match self.status {
+            CabinStatus::Available => Some(9),
+            CabinStatus::Seats(n) => Some(n),
             _ => None,
         }
     }
+
+    /// Return the service cabin of the cabin of the airline.
+    pub fn service_cabin(&self, airline: &str, map: &ServiceCabinMap) -> ServiceCabin {
+        map.service_cabin(airline, self.name)
+    }
 }
 
 impl<'a> Av<'a> {
+    /// Parse the text with the profile detected from the text.
+    /// The profile is detected from the first line of flight, so the text isn't scanned before.
     pub fn parse(text: &'a str) -> anyhow::Result<Self> {
+        Self::parse_lines(text, None)
+    }
+
+    /// Parse the text with the columns of the profile.
+    /// The lines are read in a single pass, the variant and the query are taken on the way.
+    pub fn parse_with_profile(text: &'a str, profile: &Profile) -> anyhow::Result<Self> {
+        Self::parse_lines(text, Some(profile))
+    }
+
+    /// Parse the text in a single pass, the screen of error such as `NO DISPLAY`
+    /// is recognised only when no option is found.
+    fn parse_lines(text: &'a str, profile: Option<&Profile>) -> anyhow::Result<Self> {
         if text.is_empty() {
             return Err(anyhow::Error::msg(
                 "av parameter shouldn't be empty.".to_owned(),
@@ -102,197 +609,799 @@ impl<'a> Av<'a> {
             raw_text: text,
             ..Default::default()
         };
-
-        let mut start = 0;
-        let mut index = 1;
-        loop {
-            match text.rfind(&format!("\n{}", index)) {
-                Some(end) => {
-                    let xs = &text[start..end].trim_start();
-                    start = end;
-                    if avinfo.dpt.is_none() {
-                        let _ = Self::parse_query(xs, &mut avinfo);
-                    } else {
-                        let curr_flight = Self::parse_flight(xs)?;
-                        avinfo.flights.push(curr_flight);
-                    }
+        let mut flights = AvFlights::new(text, profile.copied());
+        for flight in flights.by_ref() {
+            let flight = match flight {
+                Ok(flight) => flight,
+                Err(e) => {
+                    error::check(text)?;
+                    return Err(e);
                 }
-                _ => {
-                    let xs = &text[start..].trim_start();
-                    let curr_flight = Self::parse_flight(xs)?;
-                    avinfo.flights.push(curr_flight);
-                    break;
-                }
-            }
-            index += 1;
+            };
+            // the page marks such as `+` of `10+`, a single option on the page may have both, `1-+`.
+            let marks = flight.raw_text.as_bytes();
+            let marks = &marks[..marks.len().min(3)];
+            avinfo.has_next_page |= marks.contains(&b'+');
+            avinfo.has_prev_page |= marks.contains(&b'-');
+            avinfo.flights.push(flight);
         }
+        if !flights.found {
+            error::check(text)?;
+        }
+        if let Some((query_text, query)) = flights.query {
+            avinfo.dpt = Some(query.dpt);
+            avinfo.arr = Some(query.arr);
+            avinfo.date = Some(query.date);
+            avinfo.query = Some(query_text);
+            avinfo.av_query = Some(query);
+        }
+        avinfo.variant = match (flights.priced, flights.international) {
+            (true, _) => AvVariant::Priced,
+            (false, true) => AvVariant::International,
+            (false, false) => AvVariant::Domestic,
+        };
 
         Ok(avinfo)
     }
 
-    fn parse_query(text: &'a str, avinfo: &mut Av<'a>) -> anyhow::Result<()> {
-        match regex::Regex::new(
-            r"(?<DATE>\d{2}[A-Z]{3}(?:\d{2})?)\(([A-Z]{3})\)[\x1D\s](?<DPT>[A-Z]{3})(?<ARR>[A-Z]{3})",
-            )?
-            .captures(text)
-            {
-                Some(caps) => match (caps.name("DATE"),caps.name("DPT"), caps.name("ARR")) {
-                    (Some(date),Some(dpt), Some(arr)) => {
-                        avinfo.dpt = Some(dpt.as_str());
-                        avinfo.arr = Some(arr.as_str());
-                        avinfo.date=Some(date.as_str());
-                        avinfo.query=Some(text);
-                    }
-                    _ => {},
-                },
-                _ => {},
-            }
+    /// Return the flights of the text, which are parsed one option at a time as they're asked for,
+    /// so that the rest of the text isn't parsed after the flight needed is found.
+    /// It stops after an invalid option that [`Av::parse`] fails with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eterm_parser::av::Av;
+    ///
+    /// let text = r" 03AUG(THU) PKXSHA
+    /// 1   MU5100  DS# JA C8 YA BA HA KA LA RQ SQ TQ  PKXSHA 0900   1120   321 0^B  E  
+    /// 2   MU5102  DS# JA C8 Y2 BA HA KA LA RQ SQ TQ  PKXSHA 1300   1520   73E 0^B  E  ";
+    /// let flight = Av::flights_iter(text)
+    ///     .filter_map(|x| x.ok())
+    ///     .find(|x| x.cabin("Y").is_some_and(|c| c.is_available()))
+    ///     .unwrap();
+    /// assert_eq!(flight.flight_no, "MU5100");
+    /// assert_eq!(flight.date, Some("03AUG"));
+    /// ```
+    pub fn flights_iter(text: &'a str) -> AvFlights<'a> {
+        AvFlights::new(text, None)
+    }
+
+    ///it easy to parse a text of flight of av specifically.
+    pub fn parse_flight(text: &'a str) -> anyhow::Result<AvFlight<'a>> {
+        Self::parse_flight_with_profile(text, Profile::detect(text))
+    }
+
+    /// Parse a text of flight of av with the columns of the profile.
+    pub fn parse_flight_with_profile(
+        text: &'a str,
+        profile: &Profile,
+    ) -> anyhow::Result<AvFlight<'a>> {
+        let mut option = OptionParser::new(text, 0, profile.av_offset);
+        let mut flight = AvFlight::default();
+        for (line, kind) in lexer::av(text) {
+            option.push(&line, kind, &mut flight)?;
+        }
+        option.finish(text.len(), &mut flight);
+        Ok(flight)
+    }
+
+    fn parse_flight_row(row: Row<'a>, flight: &mut AvFlight<'a>) -> anyhow::Result<()> {
+        let c = &FLIGHT_LAYOUT.columns;
+        flight.index = lexer::option_index(row.line).unwrap_or_default();
+        flight.is_share_flight = row.is(&c.share, "*");
+        flight.flight_no = row.str(&c.flight_no);
+        flight.flight_status = row.str(&c.status);
+        Self::push_cabins(row.raw(&c.cabins), flight);
+        if let Some(dpt) = row.text(&c.dpt) {
+            flight.dpt = dpt;
+        }
+        flight.arr = row.str(&c.arr);
+        flight.take_off = row.str(&c.take_off);
+        flight.landing = row.str(&c.landing);
+        flight.landing_addday = Self::parse_addday(row.raw(&c.addday));
+        flight.model = row.str(&c.model);
+        flight.stops = row.parse::<u8>(&c.stops)?;
+        flight.asr = row.is(&c.asr, "^");
+        flight.meal = row.str(&c.meal);
+        flight.is_eticket = row.is(&c.eticket, "E");
+        flight.lowest_price = Self::parse_price(row.raw(&c.price));
         Ok(())
     }
 
-    ///it easy to parse a text of flight of av specifically. 
-    pub fn parse_flight(text: &'a str) -> anyhow::Result<AvFlight> {
-        let mut flight = AvFlight {
-            raw_text: text,
-            ..Default::default()
+    /// Parse the `>` line, such as `>   MU2104      GQ UQ ZQ        -- T3 02:20`.
+    fn parse_continuation_row(row: Row<'a>, flight: &mut AvFlight<'a>) {
+        let c = &CONTINUATION_LAYOUT.columns;
+        let terminal = |column| row.text(column).filter(|x| *x != "--");
+        flight.real_flight_no = row.text(&c.real_flight_no).or(flight.real_flight_no);
+        flight.dpt_terminal = terminal(&c.dpt_terminal).or(flight.dpt_terminal);
+        flight.arr_terminal = terminal(&c.arr_terminal).or(flight.arr_terminal);
+        flight.duration = row.text(&c.duration).or(flight.duration);
+        Self::push_cabins(row.raw(&c.cabins), flight);
+    }
+
+    /// Parse the days added to the landing time, such as `+1`.
+    fn parse_addday(text: &str) -> Option<u8> {
+        text.trim().strip_prefix('+')?.parse::<u8>().ok()
+    }
+
+    /// Return whether the line of flight is in the columns of domestic av,
+    /// the arrival at 50, the take off at 54 and the landing at 61 in the standard profile.
+    fn has_domestic_columns(row: Row) -> bool {
+        let c = &FLIGHT_LAYOUT.columns;
+        let is = |column, len, f: fn(&u8) -> bool| {
+            let x = row.raw(column).as_bytes();
+            x.len() == len && x.iter().all(f)
         };
+        is(&c.arr, 3, u8::is_ascii_uppercase)
+            && is(&c.take_off, 4, u8::is_ascii_digit)
+            && is(&c.landing, 4, u8::is_ascii_digit)
+    }
 
-        for xs in text.split("\n ") {
-            if flight.dpt.len() == 0 {
-                let _ = Self::parse_first_flight(xs, &mut flight);
-            } else {
-                flight.is_marriage_flight = true;
-                let union_flight = Self::parse_union_flight(xs, &flight)?;
-                flight.union_flights.push(union_flight);
+    /// Parse the line of flight by tokens, for the variants of av that the columns
+    /// differ from the domestic av, such as
+    /// `1- *CA937   DS# FA AA JA CA DA ZA RA YA BA MA  PEKLHR 1400   1800+1 77W 0^C  E  5200`.
+    fn parse_flight_tokens(line: &'a str, flight: &mut AvFlight<'a>) -> anyhow::Result<()> {
+        let mut tokens = line.split_whitespace().peekable();
+        let mut head = tokens.next().unwrap_or_default();
+        if let Some(index) = lexer::option_index(line) {
+            flight.index = index;
+            head = head.trim_start_matches(|c: char| c.is_ascii_digit() || c == '+' || c == '-');
+            if head.is_empty() {
+                head = tokens.next().unwrap_or_default();
             }
         }
-        Ok(flight)
+        flight.is_share_flight = head.starts_with('*');
+        flight.flight_no = head.trim_start_matches('*');
+        if let Some(status) = tokens.next_if(|x| x.len() == 3 && x.ends_with(['#', '!', '*'])) {
+            flight.flight_status = status;
+        }
+        let mut cabins = Vec::new();
+        while let Some(cabin) = tokens.next_if(|x| Self::is_cabin_token(x)) {
+            cabins.extend(AvCabin::parse(cabin, false));
+        }
+        flight.cabins.append(&mut cabins);
+        match tokens.next() {
+            Some(x) if x.len() == 6 && x.is_ascii() => {
+                flight.dpt = &x[..3];
+                flight.arr = &x[3..];
+            }
+            Some(x) if x.len() == 3 => flight.arr = x,
+            _ => {
+                return Err(anyhow::Error::msg(format!(
+                    "av flight has no city: {}",
+                    line
+                )))
+            }
+        }
+        flight.take_off = tokens.next().unwrap_or_default();
+        let landing = tokens.next().unwrap_or_default();
+        match landing.find(['+', '-']) {
+            Some(n) => {
+                flight.landing = &landing[..n];
+                flight.landing_addday = Self::parse_addday(&landing[n..]);
+            }
+            None => flight.landing = landing,
+        }
+        if let Some(addday) = tokens.next_if(|x| x.starts_with('+')) {
+            flight.landing_addday = Self::parse_addday(addday);
+        }
+        flight.model = tokens.next().unwrap_or_default();
+        if let Some(stops) = tokens.next() {
+            let (n, meal) = stops.split_at(
+                stops
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(stops.len()),
+            );
+            flight.stops = n.parse::<u8>().unwrap_or_default();
+            flight.asr = meal.starts_with('^');
+            flight.meal = meal.trim_start_matches('^');
+        }
+        flight.is_eticket = tokens.next_if_eq(&"E").is_some();
+        flight.lowest_price = tokens.last().and_then(Self::parse_price);
+        Ok(())
     }
 
-    fn parse_first_flight(text: &'a str, flight: &mut AvFlight<'a>) -> anyhow::Result<()> {
-        for line in text.lines() {
-            if regex::Regex::new(r"^\d").unwrap().is_match(line) {
-                if line.len() < 78 {
-                    continue;
-                }
-                flight.index = line[0..1].parse::<u8>()?;
-                flight.is_share_flight = &line[3..4] == "*";
-                flight.flight_no = line[4..11].trim();
-                flight.dpt = line[47..50].trim();
-                flight.arr = line[50..53].trim();
-                flight.take_off = line[54..58].trim();
-                flight.landing = line[61..65].trim();
-                flight.model = line[68..71].trim();
-                flight.stops = line[72..73].parse::<u8>()?;
-                flight.asr = &line[73..74] == "^";
-                flight.flight_status = line[12..15].trim();
-                flight.is_eticket = &line[73..74] == "E";
-                flight.meal = line[74..75].trim();
-                flight.cabins = Self::parse_cabin(&line[15..47]);
-            } else if line.starts_with(">") {
-                flight.real_flight_no = Some(line[4..11].trim());
-                match line.len() {
-                    n if n > 73 => {
-                        flight.duration = Some(&line[74..]);
-                    }
-                    n if n > 71 => {
-                        flight.arr_terminal = Some(&line[71..73]);
-                    }
-                    n if n > 68 => {
-                        flight.dpt_terminal = Some(&line[68..70]);
-                    }
-                    _ => {}
+    /// Parse the `>` line by tokens, such as `>   CA937       UA HA QA    T3 T2  11:00`.
+    fn parse_continuation_tokens(line: &'a str, flight: &mut AvFlight<'a>) {
+        let mut tokens = line[1..].split_whitespace().collect::<Vec<_>>();
+        if tokens
+            .last()
+            .is_some_and(|x| x.len() == 5 && x.as_bytes()[2] == b':')
+        {
+            flight.duration = tokens.pop();
+            // the terminals are always in pairs before the duration.
+            if tokens.len() >= 2 {
+                let terminal = |x: &'a str| Some(x).filter(|x| *x != "--");
+                flight.arr_terminal = tokens.pop().and_then(terminal).or(flight.arr_terminal);
+                flight.dpt_terminal = tokens.pop().and_then(terminal).or(flight.dpt_terminal);
+            }
+        }
+        let mut tokens = tokens.into_iter().peekable();
+        if let Some(no) = tokens.next_if(|x| !Self::is_cabin_token(x)) {
+            flight.real_flight_no = Some(no);
+        }
+        flight
+            .cabins
+            .extend(tokens.filter_map(|x| AvCabin::parse(x, false)));
+    }
+
+    /// Return whether the token is a cabin, such as `YA` and the sub cabin `M1A`.
+    fn is_cabin_token(token: &str) -> bool {
+        let bytes = token.as_bytes();
+        match bytes.len() {
+            2 => bytes[0].is_ascii_uppercase() && bytes[1].is_ascii_alphanumeric(),
+            3 => {
+                bytes[0].is_ascii_uppercase()
+                    && bytes[1].is_ascii_digit()
+                    && bytes[2].is_ascii_alphanumeric()
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse the lowest price after the columns of flight, such as `1240`, `CNY1240` and `¥1240`.
+    fn parse_price(text: &str) -> Option<f32> {
+        let token = text.split_whitespace().last()?;
+        token
+            .trim_start_matches(|c: char| !c.is_ascii_digit())
+            .parse::<f32>()
+            .ok()
+    }
+
+    ///it easy to parse a text of cabins of flight specifically.
+    pub fn parse_cabin(text: &'a str) -> Vec<AvCabin<'a>> {
+        Self::cabins(text).collect::<Vec<_>>()
+    }
+
+    /// Parse the cabins into the flight, the cabins such as `YA` are mostly 3 bytes with the space.
+    fn push_cabins(text: &'a str, flight: &mut AvFlight<'a>) {
+        flight.cabins.reserve(text.len() / 3 + 1);
+        flight.cabins.extend(Self::cabins(text));
+    }
+
+    fn cabins(text: &'a str) -> impl Iterator<Item = AvCabin<'a>> {
+        let is_sub_cabin = text.starts_with("**");
+        lexer::words(text.trim_start_matches('*'))
+            .filter_map(move |x| AvCabin::parse(x, is_sub_cabin))
+    }
+}
+
+impl Render for Av<'_> {
+    /// Render the header of the query and the options in the columns of the domestic av,
+    /// the cabins that the line of flight can't hold continue on the `>` lines,
+    /// the sub cabins are on a line after them.
+    fn render(&self) -> String {
+        let mut lines = Vec::new();
+        let mut date = None;
+        if let Some(query) = &self.av_query {
+            if let Some(command) = query.command {
+                lines.push(format!(">{}", command));
+            }
+            let mut header = format!(
+                " {}({}) {}{}",
+                query.date,
+                query.weekday.unwrap_or_default(),
+                query.dpt,
+                query.arr
+            );
+            for via in [query.via_point, query.via_carrier].into_iter().flatten() {
+                header.push_str(" VIA ");
+                header.push_str(via);
+            }
+            lines.push(header);
+            date = Some(query.date);
+        }
+        for (i, flight) in self.flights.iter().enumerate() {
+            // the date header appears again when the date of the options changes.
+            if flight.date.is_some() && flight.date != date {
+                date = flight.date;
+                let mut header = format!(
+                    " {}({})",
+                    date.unwrap_or_default(),
+                    flight.weekday.unwrap_or_default()
+                );
+                if let Some(query) = &self.av_query {
+                    header.push_str(&format!(" {}{}", query.dpt, query.arr));
                 }
-                let mut cabins = Self::parse_cabin(&line[15..61]);
-                flight.cabins.append(&mut cabins);
-            } else if line.starts_with("               **") {
-                let mut cabins = Self::parse_cabin(line[15..47].trim_end());
-                flight.cabins.append(&mut cabins);
+                lines.push(header);
+            }
+            let mut index = flight.index.to_string();
+            if i == 0 && self.has_prev_page {
+                index.push('-');
+            }
+            if i + 1 == self.flights.len() && self.has_next_page {
+                index.push('+');
+            }
+            Self::render_leg(flight, &index, None, &mut lines);
+            let mut arr = flight.arr;
+            for leg in &flight.union_flights {
+                Self::render_leg(leg, "", Some(arr), &mut lines);
+                arr = leg.arr;
             }
         }
-        Ok(())
+        lines.join("\n")
     }
+}
 
-    fn parse_union_flight(text: &'a str, flight: &AvFlight<'a>) -> anyhow::Result<AvFlight<'a>> {
-        //let mut raw_text=text.to_owned();
-        //raw_text.insert_str(0, " ");
-        //println!("{}",text);
-        let mut union_flight = AvFlight {
-            raw_text: &text,
-            ..Default::default()
-        };
-        
-        for line in union_flight.raw_text.lines() {
-            if regex::Regex::new(r"^\s+\*?[A-Z0-9]{2}\d+")?.is_match(line) {
-                union_flight.is_share_flight = &line[2..3] == "*";
-                union_flight.flight_no = line[3..10].trim();
-                union_flight.dpt = flight.arr;
-                union_flight.arr = line[49..52].trim();
-                union_flight.take_off = line[53..57].trim();
-                union_flight.landing = line[60..64].trim();
-                union_flight.model = line[67..70].trim();
-                union_flight.stops = line[71..72].parse::<u8>()?;
-                union_flight.flight_status = line[11..14].trim();
-                match line.len() {
-                    n if n > 76 => {
-                        union_flight.is_eticket = &line[76..77] == "E";
-                        union_flight.meal = line[73..74].trim();
-                    }
-                    n if n > 73 => {
-                        union_flight.meal = line[73..74].trim();
-                    }
-                    _ => {}
+impl Av<'_> {
+    /// Render the lines of a leg, the departure of the union flight is left blank
+    /// when it's the arrival of the leg before.
+    fn render_leg(leg: &AvFlight, index: &str, prev_arr: Option<&str>, lines: &mut Vec<String>) {
+        let c = &FLIGHT_LAYOUT.columns;
+        let width = |c: &Column| c.end.unwrap_or_default() - c.start;
+        let mut cabins = leg
+            .cabins
+            .iter()
+            .filter(|x| !x.is_sub_cabin)
+            .map(|x| format!("{}{}", x.name, x.state))
+            .peekable();
+        let mut row = RowWriter::default();
+        row.push(index);
+        if leg.is_share_flight {
+            row.put(&c.share, "*");
+        }
+        row.put(&c.flight_no, leg.flight_no)
+            .put(&c.status, leg.flight_status)
+            .put(
+                &c.cabins,
+                &Self::take_cabins(&mut cabins, width(&c.cabins) - 2),
+            );
+        if prev_arr != Some(leg.dpt) {
+            row.put(&c.dpt, leg.dpt);
+        }
+        row.put(&c.arr, leg.arr)
+            .put(&c.take_off, leg.take_off)
+            .put(&c.landing, leg.landing);
+        if let Some(addday) = leg.landing_addday {
+            row.put(&c.addday, &format!("+{}", addday));
+        }
+        row.put(&c.model, leg.model)
+            .put(&c.stops, &leg.stops.to_string());
+        if leg.asr {
+            row.put(&c.asr, "^");
+        }
+        row.put(&c.meal, leg.meal);
+        if leg.is_eticket {
+            row.put(&c.eticket, "E");
+        }
+        if let Some(price) = leg.lowest_price {
+            row.put(&c.price, &price.to_string());
+        }
+        lines.push(row.finish());
+
+        let c = &CONTINUATION_LAYOUT.columns;
+        let terminals = leg.dpt_terminal.is_some() || leg.arr_terminal.is_some();
+        let mut first = leg.real_flight_no.is_some() || terminals || leg.duration.is_some();
+        while first || cabins.peek().is_some() {
+            let mut row = RowWriter::default();
+            row.push(">");
+            if first {
+                row.put(&c.real_flight_no, leg.real_flight_no.unwrap_or_default());
+            }
+            row.put(
+                &c.cabins,
+                &Self::take_cabins(&mut cabins, width(&c.cabins) - 1),
+            );
+            if first && terminals {
+                row.put(&c.dpt_terminal, leg.dpt_terminal.unwrap_or("--"))
+                    .put(&c.arr_terminal, leg.arr_terminal.unwrap_or("--"));
+            }
+            if let Some(duration) = leg.duration.filter(|_| first) {
+                row.put(&c.duration, duration);
+            }
+            lines.push(row.finish());
+            first = false;
+        }
+
+        let sub_cabins = leg
+            .cabins
+            .iter()
+            .filter(|x| x.is_sub_cabin)
+            .map(|x| format!("{}{}", x.name, x.state))
+            .collect::<Vec<_>>();
+        if !sub_cabins.is_empty() {
+            let mut row = RowWriter::default();
+            row.put(&SUB_CABIN_LAYOUT.columns, "**")
+                .push(&sub_cabins.join(" "));
+            lines.push(row.finish());
+        }
+    }
+
+    /// Take the cabins that the width of column holds, each cabin follows a space,
+    /// such as ` JA C8 YA`.
+    fn take_cabins(cabins: &mut Peekable<impl Iterator<Item = String>>, width: usize) -> String {
+        let mut text = String::new();
+        // the cabin wider than the column is taken alone, so that every cabin is taken.
+        while let Some(cabin) =
+            cabins.next_if(|x| text.is_empty() || text.len() + 1 + x.len() <= width)
+        {
+            text.push(' ');
+            text.push_str(&cabin);
+        }
+        text
+    }
+}
+
+/// The iterator of the flights of av, see [`Av::flights_iter`].
+#[derive(Debug)]
+pub struct AvFlights<'a> {
+    text: &'a str,
+    lines: lexer::AvLines<'a>,
+    /// the profile given, or detected from the first line of flight.
+    profile: Option<Profile>,
+    /// the option that is parsing, with its flight.
+    option: Option<(OptionParser<'a>, AvFlight<'a>)>,
+    /// the date of the header that the options are under, the date header may appear again
+    /// when the options span a date change.
+    date: Option<&'a str>,
+    /// the weekday of the header of the date.
+    weekday: Option<&'a str>,
+    /// the query of the text before the first header, with the text.
+    query: Option<(&'a str, AvQuery<'a>)>,
+    header: bool,
+    international: bool,
+    priced: bool,
+    /// the text without option number is parsed as a single flight.
+    found: bool,
+    done: bool,
+}
+
+impl<'a> AvFlights<'a> {
+    fn new(text: &'a str, profile: Option<Profile>) -> Self {
+        Self {
+            text,
+            lines: lexer::av(text),
+            profile,
+            option: None,
+            date: None,
+            weekday: None,
+            query: None,
+            header: false,
+            international: false,
+            priced: false,
+            found: false,
+            done: text.is_empty(),
+        }
+    }
+
+    /// Finish the option that is parsing, its text ends before the offset.
+    fn finish(&mut self, end: usize) -> Option<AvFlight<'a>> {
+        let (o, mut flight) = self.option.take()?;
+        let end = o.start + self.text[o.start..end].trim_end_matches(['\r', '\n']).len();
+        o.finish(end, &mut flight);
+        self.date_flight(&mut flight);
+        Some(flight)
+    }
+
+    /// Set the date of the header that the flight is under, it's the date of the query
+    /// before the date changes.
+    fn date_flight(&self, flight: &mut AvFlight<'a>) {
+        match (self.date, &self.query) {
+            (Some(date), _) => {
+                flight.date = Some(date);
+                flight.weekday = self.weekday;
+            }
+            (None, Some((_, query))) => {
+                flight.date = Some(query.date);
+                flight.weekday = query.weekday;
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+impl<'a> Iterator for AvFlights<'a> {
+    type Item = anyhow::Result<AvFlight<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let Some((line, kind)) = self.lines.next() else {
+                self.done = true;
+                if let Some(flight) = self.finish(self.text.len()) {
+                    return Some(Ok(flight));
                 }
-                let mut cabins = Self::parse_cabin(&line[14..60]);
-                union_flight.cabins.append(&mut cabins);
-            } else if line.starts_with(">") {
-                union_flight.real_flight_no = Some(&line[4..11]);
-                match line.len() {
-                    n if n > 73 => {
-                        union_flight.duration = Some(&line[74..]);
-                    }
-                    n if n > 71 => {
-                        union_flight.arr_terminal = Some(&line[71..73]);
+                if self.found {
+                    return None;
+                }
+                let body = normalize::body(self.text).trim_end_matches(['\r', '\n']);
+                let profile = self.profile.unwrap_or_else(|| *Profile::detect(self.text));
+                return Some(
+                    Av::parse_flight_with_profile(body, &profile).map(|mut flight| {
+                        self.date_flight(&mut flight);
+                        flight
+                    }),
+                );
+            };
+            let start = line.offset;
+            let mut flight = None;
+            if let Some((o, leg)) = self.option.as_mut() {
+                if matches!(kind, AvKind::Header(_) | AvKind::Option(_)) {
+                    flight = self.finish(start);
+                } else if let Err(e) = o.push(&line, kind, leg) {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+            match kind {
+                AvKind::Header(date) => {
+                    // the text before the first header may echo the command.
+                    if !self.header {
+                        self.header = true;
+                        let query = self.text[..start + line.text.len()].trim();
+                        self.query = AvQuery::parse(query).ok().map(|x| (query, x));
                     }
-                    n if n > 68 => {
-                        union_flight.dpt_terminal = Some(&line[68..70]);
+                    self.date = Some(date);
+                    self.weekday = line
+                        .text
+                        .split_once('(')
+                        .and_then(|(_, x)| x.split_once(')'))
+                        .map(|(x, _)| x)
+                        .filter(|x| !x.is_empty());
+                }
+                AvKind::Option(_) => {
+                    self.found = true;
+                    let profile = *self.profile.get_or_insert_with(|| {
+                        *Profile::detect_av_line(line.text)
+                            .unwrap_or_else(|| Profile::detect(self.text))
+                    });
+                    let mut o = OptionParser::new(self.text, start, profile.av_offset);
+                    let mut leg = AvFlight::default();
+                    if let Err(e) = o.push(&line, kind, &mut leg) {
+                        self.done = true;
+                        return Some(Err(e));
                     }
-                    _ => {}
+                    self.international |= o.tokens;
+                    self.priced |= o.priced;
+                    self.option = Some((o, leg));
+                }
+                AvKind::Prompt if line.text.starts_with("AVH") => self.international = true,
+                _ => {}
+            }
+            if flight.is_some() {
+                return flight.map(Ok);
+            }
+        }
+        None
+    }
+}
+
+/// The parser of the lines of an option in a single pass, the first flight and its union flights
+/// are parsed into the flight in place.
+#[derive(Debug)]
+struct OptionParser<'a> {
+    text: &'a str,
+    /// the offset of the columns, see [`Profile`].
+    offset: isize,
+    /// the offset of the first line of the option.
+    start: usize,
+    /// the offset of the first line of the union flight that is parsing.
+    leg_start: usize,
+    /// the line of flight of the leg isn't in the columns of domestic av, it's parsed by tokens.
+    tokens: bool,
+    /// the line of flight of the option shows the lowest price.
+    priced: bool,
+    /// the line of flight of the option is invalid, the rest lines of it are skipped.
+    skip: bool,
+}
+
+impl<'a> OptionParser<'a> {
+    fn new(text: &'a str, start: usize, offset: isize) -> Self {
+        Self {
+            text,
+            offset,
+            start,
+            leg_start: start,
+            tokens: false,
+            priced: false,
+            skip: false,
+        }
+    }
+
+    /// Parse a line of the option, it's an error when a union flight is invalid.
+    fn push(
+        &mut self,
+        line: &Line<'a>,
+        kind: AvKind<'a>,
+        flight: &mut AvFlight<'a>,
+    ) -> anyhow::Result<()> {
+        match kind {
+            // a union flight starts at the line that is indented with a flight number.
+            AvKind::Union if line.offset > self.start => {
+                self.end_leg(line.offset, flight);
+                self.leg_start = line.offset;
+                self.skip = false;
+                flight.union_flights.push(AvFlight {
+                    dpt: flight.union_flights.last().map_or(flight.arr, |x| x.arr),
+                    ..Default::default()
+                });
+                flight.is_marriage_flight = true;
+                let union_flight = flight.union_flights.last_mut().unwrap();
+                let row = FLIGHT_LAYOUT.row_at(line.text, self.offset);
+                self.tokens = !Av::has_domestic_columns(row);
+                match self.tokens {
+                    true => Av::parse_flight_tokens(line.text, union_flight)?,
+                    false => Av::parse_flight_row(row, union_flight)?,
                 }
-                let mut cabins = Self::parse_cabin(&line[15..61]);
-                union_flight.cabins.append(&mut cabins);
-            } else if line.starts_with("               **") {
-                let mut cabins = Self::parse_cabin(line[15..47].trim_end());
-                union_flight.cabins.append(&mut cabins);
             }
+            _ if self.skip => {}
+            AvKind::Option(_) if flight.union_flights.is_empty() => {
+                let row = FLIGHT_LAYOUT.row_at(line.text, self.offset);
+                let result = if Av::has_domestic_columns(row) {
+                    self.priced |= Av::parse_price(row.raw(&FLIGHT_LAYOUT.columns.price)).is_some();
+                    Av::parse_flight_row(row, flight)
+                } else {
+                    self.tokens = true;
+                    Av::parse_flight_tokens(line.text, flight)
+                        .map(|_| self.priced |= flight.lowest_price.is_some())
+                };
+                self.skip = result.is_err();
+            }
+            AvKind::Continuation if self.tokens => {
+                Av::parse_continuation_tokens(line.text, Self::leg(flight));
+            }
+            AvKind::Continuation => {
+                let row = CONTINUATION_LAYOUT.row_at(line.text, self.offset);
+                Av::parse_continuation_row(row, Self::leg(flight));
+            }
+            AvKind::SubCabin => {
+                let row = SUB_CABIN_LAYOUT.row_at(line.text, self.offset);
+                let cabins = row.raw(&SUB_CABIN_LAYOUT.columns).trim_end();
+                Self::leg(flight).cabins.extend(Av::cabins(cabins));
+            }
+            _ => {}
         }
-        Ok(union_flight)
+        Ok(())
     }
 
-    ///it easy to parse a text of cabins of flight specifically. 
-    pub fn parse_cabin(text: &'a str) -> Vec<AvCabin> {
-        let is_sub_cabin = text.starts_with("**");
-        if is_sub_cabin {
-            text[2..]
-                .split_whitespace()
-                .into_iter()
-                .map(|x| AvCabin {
-                    name: &x[0..1],
-                    state: &x[1..1],
-                    is_sub_cabin,
-                    raw_text: x,
-                })
-                .collect::<Vec<_>>()
-        } else {
-            text.split_whitespace()
-                .into_iter()
-                .map(|x| AvCabin {
-                    name: &x[0..1],
-                    state: &x[1..1],
-                    is_sub_cabin,
-                    raw_text: x,
-                })
-                .collect::<Vec<_>>()
+    /// Return the leg that the lines are parsing into, the last union flight or the flight.
+    fn leg<'f>(flight: &'f mut AvFlight<'a>) -> &'f mut AvFlight<'a> {
+        match flight.union_flights.len().checked_sub(1) {
+            Some(i) => &mut flight.union_flights[i],
+            None => flight,
         }
     }
+
+    fn end_leg(&self, end: usize, flight: &mut AvFlight<'a>) {
+        if let Some(x) = flight.union_flights.last_mut() {
+            x.raw_text = &self.text[self.leg_start..end];
+        }
+    }
+
+    fn finish(&self, end: usize, flight: &mut AvFlight<'a>) {
+        self.end_leg(end, flight);
+        flight.raw_text = &self.text[self.start..end];
+    }
+}
+
+/// The conditions to filter the options of av, the options that a connecting option
+/// matches only when every leg of it matches.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::av::AvFilter;
+///
+/// let text = r" 03AUG(THU) PKXSHA
+/// 1   MU5100  DS# JA C8 YA BA HA KA LA RQ SQ TQ  PKXSHA 0900   1120   321 0^B  E  
+/// >               GQ UQ ZQ                                            T3 T2 02:20
+/// 2  *FM9100  DS# JA C8 Y2 BA HA KA LA RQ SQ TQ  PKXSHA 1300   1520   73E 0^B  E  
+/// >   MU5102      GQ UQ ZQ                                            T3 T2 02:20";
+/// let av = eterm_parser::parse_av(text).unwrap();
+/// let mut filter = AvFilter::default();
+/// filter.exclude_codeshare().min_seats("Y", 2);
+/// let flights = av.filter(&filter).collect::<Vec<_>>();
+/// assert_eq!(flights.len(), 1);
+/// assert_eq!(flights[0].flight_no, "MU5100");
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct AvFilter {
+    direct_only: bool,
+    exclude_codeshare: bool,
+    include_carriers: Vec<String>,
+    exclude_carriers: Vec<String>,
+    models: Vec<String>,
+    depart_after: Option<String>,
+    depart_before: Option<String>,
+    min_seats: Vec<(String, u8)>,
+    max_duration: Option<u32>,
+}
+
+impl AvFilter {
+    /// Only the options without union flights.
+    pub fn direct_only(&mut self) -> &mut Self {
+        self.direct_only = true;
+        self
+    }
+
+    /// Exclude the options that any leg is a codeshare flight.
+    pub fn exclude_codeshare(&mut self) -> &mut Self {
+        self.exclude_codeshare = true;
+        self
+    }
+
+    /// Only the options that every leg is marketed by the carriers included.
+    pub fn include_carrier(&mut self, carrier: &str) -> &mut Self {
+        self.include_carriers.push(carrier.to_owned());
+        self
+    }
+
+    /// Exclude the options that any leg is marketed by the carrier.
+    pub fn exclude_carrier(&mut self, carrier: &str) -> &mut Self {
+        self.exclude_carriers.push(carrier.to_owned());
+        self
+    }
+
+    /// Only the options that every leg is operated with the equipments, such as 321.
+    pub fn model(&mut self, model: &str) -> &mut Self {
+        self.models.push(model.to_owned());
+        self
+    }
+
+    /// Only the options that take off in the window, such as `0800` to `1200`, both are inclusive.
+    pub fn depart_between(&mut self, after: &str, before: &str) -> &mut Self {
+        self.depart_after = Some(after.to_owned());
+        self.depart_before = Some(before.to_owned());
+        self
+    }
+
+    /// Only the options that every leg has at least the seats in the booking class.
+    pub fn min_seats(&mut self, cabin: &str, seats: u8) -> &mut Self {
+        self.min_seats.push((cabin.to_owned(), seats));
+        self
+    }
+
+    /// Only the options that take at most the minutes from the first take off to the last landing.
+    pub fn max_duration(&mut self, minutes: u32) -> &mut Self {
+        self.max_duration = Some(minutes);
+        self
+    }
+
+    /// Return whether the option of av matches the conditions.
+    pub fn matches(&self, flight: &AvFlight) -> bool {
+        if self.direct_only && !flight.union_flights.is_empty() {
+            return false;
+        }
+        let take_off = flight.take_off;
+        if self
+            .depart_after
+            .as_ref()
+            .is_some_and(|x| take_off < x.as_str())
+            || self
+                .depart_before
+                .as_ref()
+                .is_some_and(|x| take_off > x.as_str())
+        {
+            return false;
+        }
+        if !flight.legs().all(|x| self.matches_leg(x)) {
+            return false;
+        }
+        match self.max_duration {
+            Some(max) => crate::journey::Journey::from_flight(flight, &Default::default())
+                .is_ok_and(|x| x.elapsed() <= max),
+            None => true,
+        }
+    }
+
+    fn matches_leg(&self, leg: &AvFlight) -> bool {
+        let carrier = leg.airline().unwrap_or_default();
+        !(self.exclude_codeshare && leg.is_share_flight)
+            && (self.include_carriers.is_empty()
+                || self.include_carriers.iter().any(|x| x == carrier))
+            && !self.exclude_carriers.iter().any(|x| x == carrier)
+            && (self.models.is_empty() || self.models.iter().any(|x| x == leg.model))
+            && self.min_seats.iter().all(|(name, seats)| {
+                leg.cabin(name)
+                    .and_then(|x| x.status.seats())
+                    .is_some_and(|x| x >= *seats)
+            })
+    }
+}
+
+impl<'a> Av<'a> {
+    /// Return the options that match the filter.
+    pub fn filter<'s>(&'s self, filter: &'s AvFilter) -> impl Iterator<Item = &'s AvFlight<'a>> {
+        self.flights.iter().filter(move |x| filter.matches(x))
+    }
 }
@@ -0,0 +1,100 @@
+use crate::response::{Response, ResponseKind};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// The statistics of the screens of a kind of response in a batch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KindStats {
+    pub count: usize,
+    pub failures: usize,
+    /// the time of parsing the screens, summed up over the threads.
+    pub elapsed: Duration,
+}
+
+/// The statistics of a batch of screens, see [`parse_batch_with_stats`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BatchStats {
+    pub total: usize,
+    pub failures: usize,
+    /// the screens that the kind of response isn't detected, which are failures too.
+    pub unknown: usize,
+    pub kinds: BTreeMap<ResponseKind, KindStats>,
+    /// the wall time of the batch.
+    pub elapsed: Duration,
+}
+
+impl BatchStats {
+    /// Return the statistics of the kind of response.
+    pub fn kind(&self, kind: ResponseKind) -> KindStats {
+        self.kinds.get(&kind).copied().unwrap_or_default()
+    }
+}
+
+/// Parse the screens with the kind of response detected from every screen,
+/// the results are in the order of the screens.
+/// The screens are parsed in parallel with the `rayon` feature, or one by one without it.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::response::{Response, ResponseKind};
+///
+/// let screens = [">RT KE9SWE\n 1.ZHANGSAN KE9SWE", "MULTI\n8L9681 /08SEP", "NO DISPLAY"];
+/// let results = eterm_parser::batch::parse_batch(&screens);
+/// assert_eq!(results.len(), 3);
+/// assert_eq!(results[1].as_ref().map(Response::kind).ok(), Some(ResponseKind::Ml));
+/// assert!(results[2].is_err());
+/// ```
+pub fn parse_batch<'a>(texts: &[&'a str]) -> Vec<anyhow::Result<Response<'a>>> {
+    parse_batch_with_stats(texts).0
+}
+
+/// Parse the screens as [`parse_batch`], with the counts, the failures and the time
+/// of every kind of response.
+pub fn parse_batch_with_stats<'a>(
+    texts: &[&'a str],
+) -> (Vec<anyhow::Result<Response<'a>>>, BatchStats) {
+    let start = Instant::now();
+    let parsed = map(texts, |text| {
+        let start = Instant::now();
+        let kind = ResponseKind::detect(text);
+        let result = match kind {
+            Some(kind) => kind.parse(text),
+            None => Response::parse(text),
+        };
+        (kind, result, start.elapsed())
+    });
+    let mut stats = BatchStats {
+        total: texts.len(),
+        ..Default::default()
+    };
+    let results = parsed
+        .into_iter()
+        .map(|(kind, result, elapsed)| {
+            stats.failures += usize::from(result.is_err());
+            match kind {
+                Some(kind) => {
+                    let x = stats.kinds.entry(kind).or_default();
+                    x.count += 1;
+                    x.failures += usize::from(result.is_err());
+                    x.elapsed += elapsed;
+                }
+                None => stats.unknown += 1,
+            }
+            result
+        })
+        .collect();
+    stats.elapsed = start.elapsed();
+    (results, stats)
+}
+
+#[cfg(feature = "rayon")]
+fn map<'a, T: Send>(texts: &[&'a str], f: impl Fn(&'a str) -> T + Sync) -> Vec<T> {
+    use rayon::prelude::*;
+    texts.par_iter().map(|x| f(x)).collect()
+}
+
+#[cfg(not(feature = "rayon"))]
+fn map<'a, T>(texts: &[&'a str], f: impl Fn(&'a str) -> T) -> Vec<T> {
+    texts.iter().map(|x| f(x)).collect()
+}
@@ -0,0 +1,37 @@
+//! The mock of eterm server that replies the screens of the fixtures in a directory.
+//!
+//! ```sh
+//! cargo run --features mock-server --bin eterm-mock-server -- fixtures --addr 127.0.0.1:3500 --latency 200
+//! ```
+
+use eterm_parser::mock::MockServer;
+use std::net::TcpListener;
+use std::time::Duration;
+
+const USAGE: &str = "usage: eterm-mock-server <fixtures dir> [--addr <addr>] [--latency <ms>]";
+
+fn main() -> anyhow::Result<()> {
+    let mut dir = None;
+    let mut addr = "127.0.0.1:3500".to_owned();
+    let mut latency = Duration::ZERO;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--addr" => addr = args.next().ok_or_else(|| anyhow::Error::msg(USAGE))?,
+            "--latency" => {
+                let ms = args.next().and_then(|x| x.parse::<u64>().ok());
+                latency = Duration::from_millis(ms.ok_or_else(|| anyhow::Error::msg(USAGE))?);
+            }
+            _ if dir.is_none() && !arg.starts_with("--") => dir = Some(arg),
+            _ => return Err(anyhow::Error::msg(USAGE)),
+        }
+    }
+    let dir = dir.ok_or_else(|| anyhow::Error::msg(USAGE))?;
+
+    let mut server = MockServer::new();
+    server.load_fixtures(&dir)?.latency(latency);
+    let listener = TcpListener::bind(&addr)?;
+    println!("eterm mock server listening on {}", listener.local_addr()?);
+    server.serve(listener)?;
+    Ok(())
+}
@@ -0,0 +1,588 @@
+use crate::av::MONTHS;
+use crate::normalize;
+use crate::response::ResponseKind;
+use std::fmt;
+
+/// The command that is sent to eterm server, rendered as the exact command line
+/// and parsed from the command echoed in the response.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::command::{AvCommand, Command};
+///
+/// let mut av = AvCommand::new("PEK", "SHA", "03AUG");
+/// av.carrier("CA");
+/// assert_eq!(av.render().unwrap(), "AV:PEKSHA/03AUG/CA");
+/// assert_eq!(Command::parse(">AV:PEKSHA/03AUG/CA").unwrap(), Command::Av(av));
+/// assert!(AvCommand::new("PEK", "SHA", "32AUG").render().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Command<'a> {
+    Av(AvCommand<'a>),
+    Detr(DetrCommand<'a>),
+    Fd(FdCommand<'a>),
+    Ml(MlCommand<'a>),
+    Pat(PatCommand<'a>),
+    Rt(RtCommand<'a>),
+}
+
+impl<'a> Command<'a> {
+    /// Parse the command line, such as `AV:PEKSHA/03AUG/CA` and the echoed `►>RT KE9SWE`,
+    /// the inputs are validated as the builders do.
+    pub fn parse(line: &'a str) -> anyhow::Result<Self> {
+        let line = normalize::lines(line).next().map_or("", |x| x.text).trim();
+        let name = &line[..line
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(line.len())];
+        let args = line[name.len()..].trim_start_matches([':', ' ']);
+        let command = match name.to_ascii_uppercase().as_str() {
+            "AV" => Self::Av(AvCommand::parse(args, false)?),
+            "AVH" => Self::Av(AvCommand::parse(args.trim_start_matches('/'), true)?),
+            "DETR" => Self::Detr(DetrCommand::parse(args)?),
+            "FD" => Self::Fd(FdCommand::parse(args)?),
+            "ML" => Self::Ml(MlCommand::parse(args)?),
+            "PAT" => Self::Pat(PatCommand::parse(args)?),
+            "RT" => Self::Rt(RtCommand::new(args)),
+            _ => {
+                return Err(anyhow::Error::msg(format!(
+                    "the command `{}` isn't supported.",
+                    line
+                )))
+            }
+        };
+        command.validate()?;
+        Ok(command)
+    }
+
+    /// Return the command echoed in the response, which pairs the response with the command.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eterm_parser::command::{Command, RtCommand};
+    ///
+    /// let text = "►>RT KE9SWE\r\n 1.ZHANGSAN KE9SWE";
+    /// assert_eq!(Command::of_response(text), Some(Command::Rt(RtCommand::new("KE9SWE"))));
+    /// assert_eq!(Command::of_response(" 1.ZHANGSAN KE9SWE"), None);
+    /// ```
+    pub fn of_response(text: &'a str) -> Option<Self> {
+        normalize::lines(text)
+            .filter(|x| x.is_prompt())
+            .find_map(|x| Self::parse(x.text).ok())
+    }
+
+    /// Return the kind of response of the command.
+    pub fn kind(&self) -> ResponseKind {
+        match self {
+            Self::Av(_) => ResponseKind::Av,
+            Self::Detr(_) => ResponseKind::Detr,
+            Self::Fd(_) => ResponseKind::Fd,
+            Self::Ml(_) => ResponseKind::Ml,
+            Self::Pat(_) => ResponseKind::Pat,
+            Self::Rt(_) => ResponseKind::Pnr,
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            Self::Av(x) => x.validate(),
+            Self::Detr(x) => x.validate(),
+            Self::Fd(x) => x.validate(),
+            Self::Ml(x) => x.validate(),
+            Self::Pat(x) => x.validate(),
+            Self::Rt(x) => x.validate(),
+        }
+    }
+
+    /// Return the command line after the inputs are validated.
+    pub fn render(&self) -> anyhow::Result<String> {
+        self.validate().map(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for Command<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Av(x) => x.fmt(f),
+            Self::Detr(x) => x.fmt(f),
+            Self::Fd(x) => x.fmt(f),
+            Self::Ml(x) => x.fmt(f),
+            Self::Pat(x) => x.fmt(f),
+            Self::Rt(x) => x.fmt(f),
+        }
+    }
+}
+
+/// The av command, such as `AV:PKXSHA/03AUG/0800/KN/D` and `AVH/PEKLHR/15SEP`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct AvCommand<'a> {
+    pub dpt: &'a str,
+    pub arr: &'a str,
+    /// the date such as 03AUG, or 03AUG23 with the year.
+    pub date: &'a str,
+    /// the city between the city pair, such as CAN of `AV:PEKCANSHA/03AUG`.
+    pub via_point: Option<&'a str>,
+    /// the time that the flights depart from, such as 0800.
+    pub time: Option<&'a str>,
+    pub carrier: Option<&'a str>,
+    pub direct_only: bool,
+    /// the international av of `AVH`.
+    pub international: bool,
+}
+
+impl<'a> AvCommand<'a> {
+    pub fn new(dpt: &'a str, arr: &'a str, date: &'a str) -> Self {
+        Self {
+            dpt,
+            arr,
+            date,
+            ..Default::default()
+        }
+    }
+
+    pub fn via_point(&mut self, city: &'a str) -> &mut Self {
+        self.via_point = Some(city);
+        self
+    }
+
+    pub fn time(&mut self, time: &'a str) -> &mut Self {
+        self.time = Some(time);
+        self
+    }
+
+    pub fn carrier(&mut self, carrier: &'a str) -> &mut Self {
+        self.carrier = Some(carrier);
+        self
+    }
+
+    pub fn direct_only(&mut self) -> &mut Self {
+        self.direct_only = true;
+        self
+    }
+
+    pub fn international(&mut self) -> &mut Self {
+        self.international = true;
+        self
+    }
+
+    /// Parse the arguments such as `PKXSHA/03AUG/0800/KN/D`.
+    fn parse(args: &'a str, international: bool) -> anyhow::Result<Self> {
+        let mut parts = args.split('/').map(str::trim);
+        let pair = parts.next().unwrap_or_default();
+        let mut command = match pair.len() {
+            6 if pair.is_ascii() => Self::new(&pair[..3], &pair[3..], ""),
+            9 if pair.is_ascii() => {
+                let mut command = Self::new(&pair[..3], &pair[6..], "");
+                command.via_point(&pair[3..6]);
+                command
+            }
+            _ => return Err(invalid("city pair", pair)),
+        };
+        command.date = parts.next().unwrap_or_default();
+        command.international = international;
+        for part in parts {
+            match part {
+                "D" => command.direct_only = true,
+                x if x.len() == 4 && x.bytes().all(|b| b.is_ascii_digit()) => {
+                    command.time = Some(x)
+                }
+                x => command.carrier = Some(x),
+            }
+        }
+        Ok(command)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        check("city", self.dpt, is_city)?;
+        check("city", self.arr, is_city)?;
+        check("date", self.date, is_date)?;
+        self.via_point
+            .map_or(Ok(()), |x| check("city", x, is_city))?;
+        self.time.map_or(Ok(()), |x| check("time", x, is_time))?;
+        self.carrier
+            .map_or(Ok(()), |x| check("carrier", x, is_carrier))
+    }
+
+    /// Return the command line after the inputs are validated.
+    pub fn render(&self) -> anyhow::Result<String> {
+        self.validate().map(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for AvCommand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.international {
+            true => f.write_str("AVH/")?,
+            false => f.write_str("AV:")?,
+        }
+        write!(
+            f,
+            "{}{}{}/{}",
+            self.dpt,
+            self.via_point.unwrap_or_default(),
+            self.arr,
+            self.date
+        )?;
+        for x in [self.time, self.carrier].into_iter().flatten() {
+            write!(f, "/{}", x)?;
+        }
+        if self.direct_only {
+            f.write_str("/D")?;
+        }
+        Ok(())
+    }
+}
+
+/// The fd command, such as `FD:KMGCTU/05SEP23/KY`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct FdCommand<'a> {
+    pub org: &'a str,
+    pub dst: &'a str,
+    /// the date such as 05SEP23, or 05SEP without the year.
+    pub date: &'a str,
+    pub carrier: Option<&'a str>,
+}
+
+impl<'a> FdCommand<'a> {
+    pub fn new(org: &'a str, dst: &'a str, date: &'a str) -> Self {
+        Self {
+            org,
+            dst,
+            date,
+            carrier: None,
+        }
+    }
+
+    pub fn carrier(&mut self, carrier: &'a str) -> &mut Self {
+        self.carrier = Some(carrier);
+        self
+    }
+
+    /// Parse the arguments such as `KMGCTU/05SEP23/KY`.
+    fn parse(args: &'a str) -> anyhow::Result<Self> {
+        let mut parts = args.split('/').map(str::trim);
+        let pair = parts.next().unwrap_or_default();
+        if pair.len() != 6 || !pair.is_ascii() {
+            return Err(invalid("city pair", pair));
+        }
+        let mut command = Self::new(&pair[..3], &pair[3..], parts.next().unwrap_or_default());
+        command.carrier = parts.next().filter(|x| !x.is_empty());
+        Ok(command)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        check("city", self.org, is_city)?;
+        check("city", self.dst, is_city)?;
+        check("date", self.date, is_date)?;
+        self.carrier
+            .map_or(Ok(()), |x| check("carrier", x, is_carrier))
+    }
+
+    /// Return the command line after the inputs are validated.
+    pub fn render(&self) -> anyhow::Result<String> {
+        self.validate().map(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for FdCommand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FD:{}{}/{}", self.org, self.dst, self.date)?;
+        if let Some(x) = self.carrier {
+            write!(f, "/{}", x)?;
+        }
+        Ok(())
+    }
+}
+
+/// The key that detr queries the tickets by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DetrKey {
+    /// the ticket number, such as `999-1234567890`.
+    Tn,
+    /// the identity number of the passenger.
+    Ni,
+    /// the pnr code.
+    Cn,
+}
+
+impl DetrKey {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Tn => "TN",
+            Self::Ni => "NI",
+            Self::Cn => "CN",
+        }
+    }
+}
+
+/// The detr command, such as `DETR:TN/999-1234567890`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DetrCommand<'a> {
+    pub key: DetrKey,
+    pub value: &'a str,
+}
+
+impl<'a> DetrCommand<'a> {
+    pub fn new(key: DetrKey, value: &'a str) -> Self {
+        Self { key, value }
+    }
+
+    pub fn ticket_no(ticket_no: &'a str) -> Self {
+        Self::new(DetrKey::Tn, ticket_no)
+    }
+
+    /// Parse the arguments such as `TN/999-1234567890`.
+    fn parse(args: &'a str) -> anyhow::Result<Self> {
+        let (key, value) = args.split_once('/').unwrap_or(("TN", args));
+        let key = match key.trim() {
+            "TN" => DetrKey::Tn,
+            "NI" => DetrKey::Ni,
+            "CN" => DetrKey::Cn,
+            x => return Err(invalid("detr key", x)),
+        };
+        Ok(Self::new(key, value.trim()))
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self.key {
+            DetrKey::Tn => check("ticket number", self.value, is_ticket_no),
+            DetrKey::Ni => check("identity number", self.value, |x| {
+                !x.is_empty() && x.bytes().all(|b| b.is_ascii_alphanumeric())
+            }),
+            DetrKey::Cn => check("pnr code", self.value, is_pnr_code),
+        }
+    }
+
+    /// Return the command line after the inputs are validated.
+    pub fn render(&self) -> anyhow::Result<String> {
+        self.validate().map(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for DetrCommand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DETR:{}/{}", self.key.as_str(), self.value)
+    }
+}
+
+/// The rt command, such as `RT:KE9SWE`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RtCommand<'a> {
+    pub pnr_code: &'a str,
+}
+
+impl<'a> RtCommand<'a> {
+    pub fn new(pnr_code: &'a str) -> Self {
+        Self { pnr_code }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        check("pnr code", self.pnr_code, is_pnr_code)
+    }
+
+    /// Return the command line after the inputs are validated.
+    pub fn render(&self) -> anyhow::Result<String> {
+        self.validate().map(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for RtCommand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RT:{}", self.pnr_code)
+    }
+}
+
+/// The pat command of all the segments, such as `PAT:A` and `PAT:A*CH` of the child.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct PatCommand<'a> {
+    /// the type of passenger, such as CH and IN.
+    pub passenger_type: Option<&'a str>,
+}
+
+impl<'a> PatCommand<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn passenger_type(&mut self, passenger_type: &'a str) -> &mut Self {
+        self.passenger_type = Some(passenger_type);
+        self
+    }
+
+    /// Parse the arguments such as `A*CH`.
+    fn parse(args: &'a str) -> anyhow::Result<Self> {
+        let (all, passenger_type) = match args.split_once('*') {
+            Some((all, x)) => (all, Some(x.trim())),
+            None => (args, None),
+        };
+        match all.trim() {
+            "A" => Ok(Self { passenger_type }),
+            x => Err(invalid("pat option", x)),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.passenger_type.map_or(Ok(()), |x| {
+            check("passenger type", x, |x| {
+                x.len() == 2 && x.bytes().all(|b| b.is_ascii_uppercase())
+            })
+        })
+    }
+
+    /// Return the command line after the inputs are validated.
+    pub fn render(&self) -> anyhow::Result<String> {
+        self.validate().map(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for PatCommand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PAT:A")?;
+        if let Some(x) = self.passenger_type {
+            write!(f, "*{}", x)?;
+        }
+        Ok(())
+    }
+}
+
+/// The ml command, such as `ML:C/8L9681/08SEP` and `ML:C/8L9681/08SEP/KMG` of the city.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct MlCommand<'a> {
+    /// the option of the passengers, such as C of the confirmed and B of the booked.
+    pub option: Option<&'a str>,
+    pub flight_no: &'a str,
+    pub date: &'a str,
+    pub city: Option<&'a str>,
+}
+
+impl<'a> MlCommand<'a> {
+    pub fn new(flight_no: &'a str, date: &'a str) -> Self {
+        Self {
+            flight_no,
+            date,
+            ..Default::default()
+        }
+    }
+
+    pub fn option(&mut self, option: &'a str) -> &mut Self {
+        self.option = Some(option);
+        self
+    }
+
+    pub fn city(&mut self, city: &'a str) -> &mut Self {
+        self.city = Some(city);
+        self
+    }
+
+    /// Parse the arguments such as `C/8L9681/08SEP/KMG`.
+    fn parse(args: &'a str) -> anyhow::Result<Self> {
+        let mut parts = args.split('/').map(str::trim).peekable();
+        let option = parts.next_if(|x| x.len() == 1);
+        let mut command = Self::new(
+            parts.next().unwrap_or_default(),
+            parts.next().unwrap_or_default(),
+        );
+        command.option = option;
+        command.city = parts.next();
+        Ok(command)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.option.map_or(Ok(()), |x| {
+            check("ml option", x, |x| {
+                x.len() == 1 && x.bytes().all(|b| b.is_ascii_uppercase())
+            })
+        })?;
+        check("flight number", self.flight_no, is_flight_no)?;
+        check("date", self.date, is_date)?;
+        self.city.map_or(Ok(()), |x| check("city", x, is_city))
+    }
+
+    /// Return the command line after the inputs are validated.
+    pub fn render(&self) -> anyhow::Result<String> {
+        self.validate().map(|_| self.to_string())
+    }
+}
+
+impl fmt::Display for MlCommand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ML:")?;
+        if let Some(x) = self.option {
+            write!(f, "{}/", x)?;
+        }
+        write!(f, "{}/{}", self.flight_no, self.date)?;
+        if let Some(x) = self.city {
+            write!(f, "/{}", x)?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(name: &str, value: &str) -> anyhow::Error {
+    anyhow::Error::msg(format!("the {} `{}` isn't valid.", name, value))
+}
+
+fn check(name: &str, value: &str, f: impl Fn(&str) -> bool) -> anyhow::Result<()> {
+    match f(value) {
+        true => Ok(()),
+        false => Err(invalid(name, value)),
+    }
+}
+
+/// The city code of 3 letters, such as PEK.
+fn is_city(x: &str) -> bool {
+    x.len() == 3 && x.bytes().all(|b| b.is_ascii_uppercase())
+}
+
+/// The carrier of 2 letters or digits, such as CA and 8L.
+fn is_carrier(x: &str) -> bool {
+    x.len() == 2
+        && x.bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+        && x.bytes().any(|b| b.is_ascii_uppercase())
+}
+
+/// The date such as 03AUG, or 03AUG23 with the year.
+fn is_date(x: &str) -> bool {
+    let day = x.get(0..2).and_then(|x| x.parse::<u8>().ok());
+    let year = x.get(5..);
+    matches!(day, Some(1..=31))
+        && x.get(2..5).is_some_and(|x| MONTHS.contains(&x))
+        && year
+            .is_some_and(|x| x.is_empty() || x.len() == 2 && x.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// The time such as 0800.
+fn is_time(x: &str) -> bool {
+    let hour = x.get(0..2).and_then(|x| x.parse::<u8>().ok());
+    let minute = x.get(2..).and_then(|x| x.parse::<u8>().ok());
+    x.len() == 4 && x.is_ascii() && matches!((hour, minute), (Some(0..=23), Some(0..=59)))
+}
+
+/// The flight number such as 8L9681 and CA1501A, the carrier and the digits with a suffix.
+fn is_flight_no(x: &str) -> bool {
+    let Some(number) = x.get(2..).filter(|_| is_carrier(&x[..2])) else {
+        return false;
+    };
+    let number = number
+        .strip_suffix(|c: char| c.is_ascii_uppercase())
+        .unwrap_or(number);
+    (1..=4).contains(&number.len()) && number.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The ticket number of 13 digits, such as 9991234567890 and 999-1234567890.
+fn is_ticket_no(x: &str) -> bool {
+    let digits = |x: &str| x.bytes().all(|b| b.is_ascii_digit());
+    match x.get(3..4) {
+        Some("-") => x.len() == 14 && digits(&x[..3]) && digits(&x[4..]),
+        _ => x.len() == 13 && digits(x),
+    }
+}
+
+/// The pnr code of 5 or 6 letters or digits, such as KE9SWE.
+fn is_pnr_code(x: &str) -> bool {
+    (5..=6).contains(&x.len())
+        && x.bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+}
@@ -1,8 +1,9 @@
-use crate::util;
+use crate::render::Render;
+use crate::{error, normalize, util};
 use std::collections::HashMap;
 
 /// The result that detr text parsed.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct Detr<'a> {
     /// airline issued by.
     pub issued_by: Option<&'a str>,
@@ -49,7 +50,7 @@ pub struct DetrFareItem<'a> {
     pub is_exempt: bool,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct DetrFlightSeg<'a> {
     /// the flag that show how long to transit. such as O is more than 24 hours,X is less than 24 hours.
     pub transit_flag: Option<&'a str>,
@@ -123,10 +124,12 @@ impl<'a> Detr<'a> {
                 "detr parameter shouldn't be empty.".to_owned(),
             ));
         }
+        error::check(text)?;
+        let text = normalize::body(text);
         //let finalDest = Self::regex_extact(r"\s+TO: ([A-Z]{3})\s", &text)?;
-        let re = regex::Regex::new(
+        let re = util::regex!(
             r"(?<TRANSITFLAG>[O|X]) (FM|TO):(?<INDEX>\d)(?<ORG>[A-Z]{3}) (?<AIRLINE>\w{2}) (?<CARRIER>..{2}) *(?<FLIGHTNO>\d+|OPEN)\s+(?<CABIN>[A-Z]) (?<DETPDATE>\d{2}[A-Z]{3}|OPEN ) (?<DEPTTIME>.{4}) (?<SEATSTATUS>.{2}) (?<FAREBASIS>.{10}) (?<NVB>.{6}).(?<NVA>.{6}) (?<BAGGAGE>.{3}) (?<TICKETSTATUS>[^\r\n]+)(\r|\n)+.....(?<ORGTERMINAL>..)(?<DSTTERMINAL>..) RL:(?:(?<BPNR>\w{6})(\s+)/((?<CPNR>\w{6})(?<SYSTEM>\w{2}))?)?(\s*[\r|\n]+\s+)TO:\s+(?<DST>[A-Z]{3})",
-        )?;
+        );
         let detr_flight_segs = re
             .captures_iter(text)
             .filter_map(|caps| {
@@ -217,7 +220,7 @@ impl<'a> Detr<'a> {
             })
             .collect::<Vec<_>>();
 
-        let re = regex::Regex::new(r"FARE:\s+(?<CURRENCY>[A-Z]{3})\s*(?<AMOUNT>\d+\.\d{2})\|")?;
+        let re = util::regex!(r"FARE:\s+(?<CURRENCY>[A-Z]{3})\s*(?<AMOUNT>\d+\.\d{2})\|");
         let fare = match re.captures(text) {
             Some(caps) => match (caps.name("CURRENCY"), caps.name("AMOUNT")) {
                 (Some(cap2), Some(cap3)) => Some(DetrFareItem {
@@ -231,7 +234,7 @@ impl<'a> Detr<'a> {
             _ => None,
         };
 
-        let re = regex::Regex::new(r"TOTAL:\s+(?<CURRENCY>[A-Z]{3})\s*(?<AMOUNT>\d+\.\d{2})\|")?;
+        let re = util::regex!(r"TOTAL:\s+(?<CURRENCY>[A-Z]{3})\s*(?<AMOUNT>\d+\.\d{2})\|");
         let total = match re.captures(text) {
             Some(caps) => match (caps.name("CURRENCY"), caps.name("AMOUNT")) {
                 (Some(cap2), Some(cap3)) => Some(DetrFareItem {
@@ -245,9 +248,9 @@ impl<'a> Detr<'a> {
             _ => None,
         };
 
-        let re = regex::Regex::new(
+        let re = util::regex!(
             r"TAX:\s+(?:(?<EXEMPT>EXEMPT)|(?<CURRENCY>[A-Z]{3})\s*(?<PRICE>\d+\.\d{2}))(?<TYPE>[A-Z]{2})\|",
-        )?;
+        );
 
         let items = re
             .captures_iter(text)
@@ -259,16 +262,16 @@ impl<'a> Detr<'a> {
                     caps.name("PRICE"),
                 ) {
                     (Some(cap_type), cap_exempt, cap_curr, cap_price) => Some(DetrFareItem {
-                        amount: cap_price.map_or(None, |x| x.as_str().parse::<f32>().ok()),
+                        amount: cap_price.and_then(|x| x.as_str().parse::<f32>().ok()),
                         currency: util::regex_extact_text(cap_curr),
-                        is_exempt: cap_exempt.map_or(false, |x| x.as_str() == "EXEMPT"),
+                        is_exempt: cap_exempt.is_some_and(|x| x.as_str() == "EXEMPT"),
                         item_type: Some(cap_type.as_str()),
                     }),
                     _ => None,
                 }
             })
             .collect::<Vec<_>>();
-        let taxs = if items.len() == 0 {
+        let taxs = if items.is_empty() {
             None
         } else {
             let mut map = HashMap::new();
@@ -279,24 +282,156 @@ impl<'a> Detr<'a> {
         };
 
         Ok(Self {
-            issued_by: util::regex_extact(r"\bISSUED BY: ?(.*)ORG/DST:", text)?,
-            org: util::regex_extact(r"ORG/DST: ?([A-Z]{3})/[A-Z]{3}", text)?,
-            dst: util::regex_extact(r"ORG/DST: ?[A-Z]{3}/([A-Z]{3})", text)?,
-            et: util::regex_extact(r"ORG/DST: ?[A-Z]{3}/[A-Z]{3}\s+([A-Z\-]+)", text)?,
-            er: util::regex_extact(r"E/R: ?(.*?)(\r|\n)+", text)?,
-            tour_code: util::regex_extact(r"TOUR CODE: ?(\S[^\r]*?)(\r|\n)+", text)?,
+            issued_by: util::regex_extact(util::regex!(r"\bISSUED BY: ?(.*)ORG/DST:"), text),
+            org: util::regex_extact(util::regex!(r"ORG/DST: ?([A-Z]{3})/[A-Z]{3}"), text),
+            dst: util::regex_extact(util::regex!(r"ORG/DST: ?[A-Z]{3}/([A-Z]{3})"), text),
+            et: util::regex_extact(util::regex!(r"ORG/DST: ?[A-Z]{3}/[A-Z]{3}\s+([A-Z\-]+)"), text),
+            er: util::regex_extact(util::regex!(r"E/R: ?(.*?)(\r|\n)+"), text),
+            tour_code: util::regex_extact(util::regex!(r"TOUR CODE: ?(\S[^\r]*?)(\r|\n)+"), text),
             receipt_printed: text.contains("RECEIPT PRINTED"),
-            passenger: util::regex_extact(r"PASSENGER: ?(\S[^\r]*?)(\r|\n)+", text)?,
-            exchange: util::regex_extact(r"EXCH: ?(\S[^\r]*?)\S", text)?,
-            conj_tickets: util::regex_extact(r"CONJ TKT: ?(\S[^\r]*?)(\r|\n)+", text)?,
+            passenger: util::regex_extact(util::regex!(r"PASSENGER: ?(\S[^\r]*?)(\r|\n)+"), text),
+            exchange: util::regex_extact(util::regex!(r"EXCH: ?(\S[^\r]*?)\S"), text),
+            conj_tickets: util::regex_extact(util::regex!(r"CONJ TKT: ?(\S[^\r]*?)(\r|\n)+"), text),
             flight_segs: detr_flight_segs,
-            fc: util::regex_extact(r"FC: ?(\S[^\r]*?)(\r|\n)+", text)?,
+            fc: util::regex_extact(util::regex!(r"FC: ?(\S[^\r]*?)(\r|\n)+"), text),
             fare,
             taxs,
             total,
-            fop: util::regex_extact(r"\|FOP: ?(\S[^\r]*?)(\r|\n)+", text)?,
-            oi: util::regex_extact(r"\|OI: ?(\S[^\r]*?)(\r|\n)+", text)?,
-            tktn: util::regex_extact(r"\|TKTN: ?(\S[^\r]*?)(\r|\n)+", text)?,
+            fop: util::regex_extact(util::regex!(r"\|FOP: ?(\S[^\r]*?)(\r|\n)+"), text),
+            oi: util::regex_extact(util::regex!(r"\|OI: ?(\S[^\r]*?)(\r|\n)+"), text),
+            tktn: util::regex_extact(util::regex!(r"\|TKTN: ?(\S[^\r]*?)(\r|\n)+"), text),
         })
     }
 }
+
+impl Render for Detr<'_> {
+    /// Render the ticket, every segment is followed by the line of its destination
+    /// such as `  TO: XIY`, the fare, the taxes and the total are followed by
+    /// the payment, the OI and the ticket number after `|`.
+    fn render(&self) -> String {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        if let Some(x) = self.issued_by {
+            line.push_str(&format!("ISSUED BY: {:<26}", x));
+        }
+        if let (Some(org), Some(dst)) = (self.org, self.dst) {
+            line.push_str(&format!("ORG/DST: {}/{}", org, dst));
+            if let Some(et) = self.et {
+                line.push_str(&format!("{:17}{}", "", et));
+            }
+        }
+        lines.push(line);
+        let labels = [
+            ("E/R:", self.er),
+            ("TOUR CODE:", self.tour_code),
+            ("PASSENGER:", self.passenger),
+        ];
+        for (label, value) in labels {
+            if let Some(x) = value {
+                lines.push(format!("{} {}", label, x));
+            }
+        }
+        // the exchange is read up to the text that follows it.
+        lines.push(match self.exchange {
+            Some(x) => format!(
+                "EXCH: {}CONJ TKT: {}",
+                x,
+                self.conj_tickets.unwrap_or_default()
+            ),
+            None => format!(
+                "EXCH:{:31}CONJ TKT: {}",
+                "",
+                self.conj_tickets.unwrap_or_default()
+            ),
+        });
+        if self.receipt_printed {
+            lines.push("RECEIPT PRINTED".to_owned());
+        }
+        for seg in &self.flight_segs {
+            seg.render(&mut lines);
+        }
+        if let Some(x) = self.fc {
+            lines.push(format!("FC: {}", x));
+        }
+        let amount = |x: &DetrFareItem| {
+            let price = x.amount.map_or(String::new(), util::format_price);
+            format!("{} {:>7}", x.currency.unwrap_or_default(), price)
+        };
+        let mut prices = Vec::new();
+        if let Some(x) = &self.fare {
+            prices.push(format!("FARE:{:>22}", amount(x)));
+        }
+        let mut taxs = self
+            .taxs
+            .iter()
+            .flatten()
+            .map(|(_, x)| x)
+            .collect::<Vec<_>>();
+        taxs.sort_by_key(|x| x.item_type);
+        for x in taxs {
+            let tax = match x.is_exempt {
+                true => "EXEMPT".to_owned(),
+                false => amount(x),
+            };
+            prices.push(format!(
+                "TAX:{:>21}{}",
+                tax,
+                x.item_type.unwrap_or_default()
+            ));
+        }
+        if let Some(x) = &self.total {
+            prices.push(format!("TOTAL:{:>21}", amount(x)));
+        }
+        let payments = [("FOP:", self.fop), ("OI: ", self.oi), ("TKTN: ", self.tktn)];
+        for (i, (label, value)) in payments.into_iter().enumerate() {
+            match (prices.get_mut(i), value) {
+                (Some(line), value) => {
+                    line.push_str(&format!("|{}{}", label, value.unwrap_or_default()))
+                }
+                (None, Some(value)) => prices.push(format!("|{}{}", label, value)),
+                (None, None) => {}
+            }
+        }
+        lines.extend(prices);
+        lines.iter().map(|x| format!("{}\n", x)).collect()
+    }
+}
+
+impl DetrFlightSeg<'_> {
+    /// Render the lines of segment, such as
+    /// `O FM:1HET CA    8113  S 21MAY 0815 OK S                        20K OPEN FOR USE`.
+    fn render(&self, lines: &mut Vec<String>) {
+        lines.push(format!(
+            "{} FM:{}{} {} {:3}{:>4}  {} {:5} {:4} {:2} {:10} {:6} {:6} {:3} {}",
+            self.transit_flag.unwrap_or_default(),
+            self.index.unwrap_or_default(),
+            self.org.unwrap_or_default(),
+            self.airline.unwrap_or_default(),
+            self.carrier.unwrap_or_default(),
+            self.flight_no.unwrap_or_default(),
+            self.flight_class.unwrap_or_default(),
+            self.flight_deptdate.unwrap_or_default(),
+            self.flight_depttime.unwrap_or_default(),
+            self.seat_status.unwrap_or_default(),
+            self.fare_basis.unwrap_or_default(),
+            self.nvb.unwrap_or_default(),
+            self.nva.unwrap_or_default(),
+            self.baggage.unwrap_or_default(),
+            self.ticket_status.unwrap_or_default(),
+        ));
+        let mut rl = String::new();
+        if let Some(bpnr) = self.bpnr {
+            rl.push_str(&format!("{}  /", bpnr));
+            if let (Some(cpnr), Some(system)) = (self.cpnr, self.system) {
+                rl.push_str(&format!("{}{}", cpnr, system));
+            }
+        }
+        lines.push(format!(
+            "     {:2}{:2} RL:{}",
+            self.org_term.unwrap_or_default(),
+            self.dst_term.unwrap_or_default(),
+            rl
+        ));
+        lines.push(format!("  TO: {}", self.dst.unwrap_or_default()));
+    }
+}
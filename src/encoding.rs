@@ -0,0 +1,137 @@
+use std::fmt;
+
+/// The encoding of the bytes that eterm server response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Gbk,
+    /// the GBK with the 4 bytes sequences, such as the rare chinese names.
+    Gb18030,
+}
+
+impl Encoding {
+    /// Detect the encoding, the valid UTF-8 is always UTF-8,
+    /// otherwise it's GB18030 when any 4 bytes sequence is found, else GBK.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if std::str::from_utf8(bytes).is_ok() {
+            return Self::Utf8;
+        }
+        let mut i = 0;
+        while i < bytes.len() {
+            match (bytes[i], bytes.get(i + 1)) {
+                (0x81..=0xFE, Some(0x30..=0x39)) => return Self::Gb18030,
+                (0x81..=0xFE, Some(_)) => i += 2,
+                _ => i += 1,
+            }
+        }
+        Self::Gbk
+    }
+}
+
+/// The sequence of bytes that can't be decoded, which is replaced with `U+FFFD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    /// the offset of the sequence in the bytes.
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "undecodable {} bytes at offset {}.",
+            self.len, self.offset
+        )
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// The result that parsed from the bytes, with the encoding detected and the undecodable sequences.
+#[derive(Debug)]
+pub struct Decoded<T> {
+    pub value: T,
+    pub encoding: Encoding,
+    pub errors: Vec<DecodeError>,
+}
+
+/// Decode the bytes with the encoding detected, the valid UTF-8 is borrowed without copying,
+/// otherwise the text is decoded into the buffer.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::encoding::{decode, Encoding};
+///
+/// let mut buf = String::new();
+/// let (text, encoding, errors) = decode(b" 1.\xca\xaf\xb7\xe7\xdc\xbf KE9SWE", &mut buf);
+/// assert_eq!(text, " 1.石风芸 KE9SWE");
+/// assert_eq!(encoding, Encoding::Gbk);
+/// assert!(errors.is_empty());
+/// ```
+pub fn decode<'a>(bytes: &'a [u8], buf: &'a mut String) -> (&'a str, Encoding, Vec<DecodeError>) {
+    let encoding = Encoding::detect(bytes);
+    let (text, errors) = decode_with(bytes, buf, encoding);
+    (text, encoding, errors)
+}
+
+/// Decode the bytes with the encoding given.
+pub fn decode_with<'a>(
+    bytes: &'a [u8],
+    buf: &'a mut String,
+    encoding: Encoding,
+) -> (&'a str, Vec<DecodeError>) {
+    if encoding == Encoding::Utf8 {
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return (text, Vec::new());
+        }
+    }
+    let mut decoder = match encoding {
+        Encoding::Utf8 => encoding_rs::UTF_8.new_decoder_without_bom_handling(),
+        // the decoder of GB18030 decodes GBK too.
+        Encoding::Gbk | Encoding::Gb18030 => {
+            encoding_rs::GB18030.new_decoder_without_bom_handling()
+        }
+    };
+    let mut errors = Vec::new();
+    let mut read = 0;
+    buf.clear();
+    loop {
+        let rest = bytes.len() - read;
+        buf.reserve(
+            decoder
+                .max_utf8_buffer_length_without_replacement(rest)
+                .unwrap_or(rest * 3),
+        );
+        let (result, n) = decoder.decode_to_string_without_replacement(&bytes[read..], buf, true);
+        read += n;
+        match result {
+            encoding_rs::DecoderResult::InputEmpty => break,
+            encoding_rs::DecoderResult::OutputFull => {}
+            encoding_rs::DecoderResult::Malformed(len, extra) => {
+                let len = usize::from(len);
+                errors.push(DecodeError {
+                    offset: read - usize::from(extra) - len,
+                    len,
+                });
+                buf.push('\u{FFFD}');
+            }
+        }
+    }
+    (buf.as_str(), errors)
+}
+
+/// Decode the bytes, then parse the text with the parser.
+pub(crate) fn parse<'a, T>(
+    bytes: &'a [u8],
+    buf: &'a mut String,
+    parse: fn(&'a str) -> anyhow::Result<T>,
+) -> anyhow::Result<Decoded<T>> {
+    let (text, encoding, errors) = decode(bytes, buf);
+    Ok(Decoded {
+        value: parse(text)?,
+        encoding,
+        errors,
+    })
+}
@@ -0,0 +1,156 @@
+use crate::normalize;
+
+/// The most lines of the screen of error, the screen of more lines is a response,
+/// which may contain the words of error such as `UNABLE` in the text of its items.
+const MAX_ERROR_LINES: usize = 3;
+
+/// The code of the error that eterm host replies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HostErrorCode {
+    /// `NO DISPLAY`, nothing is found of the query.
+    NoDisplay,
+    /// `CHECK CONTINUITY`, the segments of pnr aren't continuous.
+    CheckContinuity,
+    /// `UNABLE`, the host is unable to do the command now.
+    Unable,
+    /// `ELE NBR`, the number of element doesn't exist.
+    EleNbr,
+    /// `SIMULTANEOUS CHANGES TO PNR`, the pnr is changed by another at the same time.
+    SimultaneousChanges,
+    /// `NO PNR`, the pnr doesn't exist.
+    NoPnr,
+    /// `PROT SET`, the pnr or the ticket is protected from the office.
+    ProtSet,
+    /// `AUTHORITY`, the agent has no authority of the command.
+    Authority,
+}
+
+/// The catalogue of the errors, the code, the text that the line of error starts with and the hint.
+const CATALOGUE: [(HostErrorCode, &str, &str); 8] = [
+    (
+        HostErrorCode::NoDisplay,
+        "NO DISPLAY",
+        "nothing is found, check the date, the city pair or the flight.",
+    ),
+    (
+        HostErrorCode::CheckContinuity,
+        "CHECK CONTINUITY",
+        "the segments aren't continuous, add an ARNK segment or correct the cities.",
+    ),
+    (
+        HostErrorCode::Unable,
+        "UNABLE",
+        "the host is unable to do it now, retry later.",
+    ),
+    (
+        HostErrorCode::EleNbr,
+        "ELE NBR",
+        "the element number doesn't exist, display the pnr again and retry.",
+    ),
+    (
+        HostErrorCode::SimultaneousChanges,
+        "SIMULTANEOUS CHANGES TO PNR",
+        "the pnr is changed by another, ignore it with IG, display it again and retry.",
+    ),
+    (
+        HostErrorCode::NoPnr,
+        "NO PNR",
+        "the pnr doesn't exist or is in another system.",
+    ),
+    (
+        HostErrorCode::ProtSet,
+        "PROT SET",
+        "the pnr or the ticket is protected, ask its office for the access.",
+    ),
+    (
+        HostErrorCode::Authority,
+        "AUTHORITY",
+        "the agent has no authority of the command, sign in with the one that has.",
+    ),
+];
+
+impl HostErrorCode {
+    /// Return the text that the line of error starts with, such as `NO PNR`.
+    pub fn as_str(&self) -> &'static str {
+        CATALOGUE
+            .iter()
+            .find_map(|(code, text, _)| if code == self { Some(*text) } else { None })
+            .unwrap_or_default()
+    }
+}
+
+/// The error or the information that eterm host replies instead of the response,
+/// such as `NO PNR` of `RT KE9SWE`.
+///
+/// The parsers return it as the error, which is got by downcasting.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::error::{HostError, HostErrorCode};
+///
+/// let e = eterm_parser::parse_pnr(">RT KE9SWE\r\nNO PNR").unwrap_err();
+/// let e = e.downcast_ref::<HostError>().unwrap();
+/// assert_eq!(e.code, HostErrorCode::NoPnr);
+/// assert_eq!(e.message, "NO PNR");
+/// assert!(!e.is_retryable());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostError {
+    pub code: HostErrorCode,
+    /// the line of error, such as `UNABLE TO PROCESS`.
+    pub message: String,
+    /// the hint of what to do with the error.
+    pub hint: &'static str,
+}
+
+impl HostError {
+    /// Recognise the error of the screen, None when the screen is a response.
+    pub fn recognise(text: &str) -> Option<Self> {
+        let mut lines = normalize::screen_lines(text)
+            .map(|x| x.trim().trim_end_matches('+').trim_end())
+            .filter(|x| !x.is_empty());
+        let lines = lines.by_ref().take(MAX_ERROR_LINES + 1).collect::<Vec<_>>();
+        if lines.len() > MAX_ERROR_LINES {
+            return None;
+        }
+        lines.iter().find_map(|line| {
+            CATALOGUE.iter().find_map(|(code, prefix, hint)| {
+                line.starts_with(prefix).then(|| Self {
+                    code: *code,
+                    message: line.to_string(),
+                    hint,
+                })
+            })
+        })
+    }
+
+    /// Return whether the command may succeed when it's sent again later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.code,
+            HostErrorCode::Unable | HostErrorCode::SimultaneousChanges
+        )
+    }
+
+    /// Return whether the command needs signing in with another agent.
+    pub fn needs_sign_in(&self) -> bool {
+        matches!(self.code, HostErrorCode::Authority | HostErrorCode::ProtSet)
+    }
+}
+
+impl std::fmt::Display for HostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "eterm host replied {}, {}", self.message, self.hint)
+    }
+}
+
+impl std::error::Error for HostError {}
+
+/// Return the error of the screen that eterm host replies instead of the response.
+pub(crate) fn check(text: &str) -> anyhow::Result<()> {
+    match HostError::recognise(text) {
+        Some(e) => Err(e.into()),
+        None => Ok(()),
+    }
+}
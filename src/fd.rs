@@ -1,4 +1,10 @@
-#[derive(Default, Debug)]
+use crate::error;
+use crate::layout::RowWriter;
+use crate::lexer::{self, FdKind};
+use crate::render::Render;
+use crate::util;
+
+#[derive(Default, Debug, PartialEq)]
 pub struct Fd<'a> {
     pub org: Option<&'a str>,
     pub dst: Option<&'a str>,
@@ -12,170 +18,164 @@ pub struct Fd<'a> {
 }
 
 impl<'a> Fd<'a> {
+    /// Parse the text in a single pass, the percentage prices are taken from the Y prices
+    /// of the carrier that are read before.
     pub fn parse(text: &'a str) -> anyhow::Result<Self> {
         if text.is_empty() {
             return Err(anyhow::Error::msg(
                 "fd parameter shouldn't be empty.".to_owned(),
             ));
         }
-        let lines = text.split(&['\r', '\n']);
-
         let mut fdinfo = Self {
             raw_text: text,
             ..Default::default()
         };
-        for line in lines {
-            if line.starts_with(">") {
-                continue;
-            } else if line.starts_with("PAGE") {
-                continue;
-            } else if line.trim().is_empty() {
-                continue;
-            } else if line.starts_with("FD:") {
-                match regex::Regex::captures(
-                    &regex::Regex::new(
-                        r"(FD(?<COMMAND>.*)?)\s+/(?<CURRENCY>[^/]*)/TPM\s*(?<TPM>\d+)?",
-                    )?,
-                    line,
-                ) {
-                    Some(caps) => match (
-                        caps.name("COMMAND"),
-                        caps.name("CURRENCY"),
-                        caps.name("TPM"),
-                    ) {
-                        (Some(command), Some(currency), tpm) => {
-                            fdinfo.command = Some(command.as_str());
-                            fdinfo.currency = Some(currency.as_str());
-                            fdinfo.tpm =tpm.and_then(|x| Some(x.as_str().trim()));// crate::regex_extact_text(tpm);
-
-                            match regex::Regex::captures(
-                                &regex::Regex::new(
-                                    r"(?<ORG>[A-Z]{3})(?<DST>[A-Z]{3})/(?<QUERYTIME>\d{2}\w{3}(?:\d{2})?)(?:/(?<AIRLINE>\w{2}))?",
-                                )?,
-                                command.as_str(),
-                            ) {
-                                Some(ccaps) => match (
-                                    ccaps.name("ORG"),
-                                    ccaps.name("DST"),
-                                    ccaps.name("QUERYTIME"),
-                                    ccaps.name("AIRLINE"),
-                                ) {
-                                    (Some(org), Some(dst), Some(querytime), airline) => {
-                                        fdinfo.org = Some(org.as_str());
-                                        fdinfo.dst = Some(dst.as_str());
-                                        fdinfo.query_time = Some(querytime.as_str());
-                                        fdinfo.airline = airline.and_then(|x| Some(x.as_str().trim()));//crate::regex_extact_text(airline);
-                                    }
-                                    _ => {}
-                                },
-                                _ => {}
-                            }
-                        }
-                        _ => {}
-                    },
-                    _ => {}
-                }
-            } else {
-                let mut item = FdItem {
-                    ..Default::default()
-                };
-                let mut arr = line.split('/');
-                let index_airline = arr.next();
-                item.index = index_airline.and_then(|x| x[0..3].parse::<u8>().ok());
-                item.carrier = index_airline.and_then(|x| Some(x[3..5].trim()));
-                item.ticket_type = arr.next().and_then(|x| Some(x.trim()));
-                if let Some(ow_rt_price) = arr.next() {
-                    let mut ow_rt_price = ow_rt_price.split('=');
-                    item.ow_price_raw = ow_rt_price.next().and_then(|x| Some(x.trim()));
-                    item.rt_price_raw = ow_rt_price.next().and_then(|x| Some(x.trim()));
-                }
-                item.cabin = arr.next().and_then(|x| Some(x.trim()));
-                item.class = arr.next().and_then(|x| Some(x.trim()));
-                arr.next();
-                item.begin_date = arr.next().and_then(|x| {
-                    if x == "." {
-                        fdinfo.query_time
-                    } else {
-                        Some(x)
-                    }
-                });
-                item.end_date = arr.next().and_then(|x| Some(x.trim()));
-                item.policy_no = arr.next().and_then(|x| Some(x[0..6].trim()));
-
-                if item.index == Some(0u8) {
-                    continue;
-                }
-                match &item.ow_price_raw {
-                    Some(ow_price_raw) => {
-                        if ow_price_raw.contains('%') {
-                            if let Some(yfd) = fdinfo.items.as_ref().and_then(|x| {
-                                x.iter().find_map(|n| {
-                                    if n.carrier == item.carrier
-                                        && n.cabin == Some("Y")
-                                        && n.ow_price.is_some()
-                                    {
-                                        n.ow_price
-                                    } else {
-                                        None
-                                    }
-                                })
-                            }) {
-                                item.ow_price = Some(
-                                    yfd * ow_price_raw
-                                        .trim_end_matches('%')
-                                        .parse::<f32>()
-                                        .unwrap()
-                                        / 100.0,
-                                );
-                            }
-                        } else {
-                            item.ow_price = ow_price_raw.parse::<f32>().ok();
-                        }
+        let mut y_prices: Vec<YPrice> = Vec::new();
+        // the screen of error such as `NO DISPLAY` is recognised only when no fare is found.
+        let mut found = false;
+        for (line, kind) in lexer::fd(text) {
+            match kind {
+                FdKind::Header => Self::parse_header(line, &mut fdinfo),
+                FdKind::Item => {
+                    let mut item = FdItem::parse(line, fdinfo.query_time);
+                    if item.index == Some(0u8) {
+                        continue;
                     }
-                    _ => {}
-                }
-                match &item.rt_price_raw {
-                    Some(rt_price_raw) => {
-                        if rt_price_raw.contains('%') {
-                            if let Some(yfd) = fdinfo.items.as_ref().and_then(|x| {
-                                x.iter().find_map(|n| {
-                                    if n.carrier == item.carrier
-                                        && n.cabin == Some("Y")
-                                        && n.rt_price.is_some()
-                                    {
-                                        n.rt_price
-                                    } else {
-                                        None
-                                    }
-                                })
-                            }) {
-                                item.rt_price = Some(
-                                    yfd * rt_price_raw
-                                        .trim_end_matches('%')
-                                        .parse::<f32>()
-                                        .unwrap()
-                                        / 100.0,
-                                );
+                    found |= item.index.is_some();
+                    let y = y_prices.iter().find(|x| x.carrier == item.carrier);
+                    item.ow_price = FdItem::parse_price(item.ow_price_raw, y.and_then(|x| x.ow));
+                    item.rt_price = FdItem::parse_price(item.rt_price_raw, y.and_then(|x| x.rt));
+                    if item.cabin == Some("Y") {
+                        match y_prices.iter_mut().find(|x| x.carrier == item.carrier) {
+                            Some(y) => {
+                                y.ow = y.ow.or(item.ow_price);
+                                y.rt = y.rt.or(item.rt_price);
                             }
-                        } else {
-                            item.rt_price = rt_price_raw.parse::<f32>().ok();
+                            None => y_prices.push(YPrice {
+                                carrier: item.carrier,
+                                ow: item.ow_price,
+                                rt: item.rt_price,
+                            }),
                         }
                     }
-                    _ => {}
+                    fdinfo.items.get_or_insert(Vec::new()).push(item);
                 }
-                fdinfo
-                    .items
-                    .as_mut()
-                    .get_or_insert(&mut Vec::new())
-                    .push(item);
+                FdKind::Other => {}
             }
         }
+        if !found {
+            error::check(text)?;
+        }
 
         Ok(fdinfo)
     }
+
+    /// Parse the header, such as `FD:KMGCTU/05SEP23/KY     /CNY /TPM   744/`,
+    /// the command is the text before the whitespaces that the currency follows.
+    fn parse_header(line: &'a str, fdinfo: &mut Self) {
+        for tpm in line.rmatch_indices("/TPM").map(|(i, _)| i) {
+            let Some(slash) = line[..tpm].rfind('/') else {
+                continue;
+            };
+            let Some(space) = line[..slash]
+                .chars()
+                .next_back()
+                .filter(|c| c.is_whitespace())
+            else {
+                continue;
+            };
+            let Some(command) = line.get(2..slash - space.len_utf8()) else {
+                continue;
+            };
+            let digits = line[tpm + 4..].trim_start();
+            let n = digits.bytes().take_while(u8::is_ascii_digit).count();
+            fdinfo.command = Some(command);
+            fdinfo.currency = Some(&line[slash + 1..tpm]);
+            fdinfo.tpm = Some(&digits[..n]).filter(|x| !x.is_empty());
+            Self::parse_command(command, fdinfo);
+            return;
+        }
+    }
+
+    /// Parse the query of the command, such as `:KMGCTU/05SEP23/KY`.
+    fn parse_command(command: &'a str, fdinfo: &mut Self) {
+        let bytes = command.as_bytes();
+        let is = |range: std::ops::Range<usize>, f: fn(&u8) -> bool| {
+            bytes.get(range).is_some_and(|x| x.iter().all(f))
+        };
+        // the word chars such as the month, return the end of them.
+        let words = |start: usize, n: usize| {
+            let mut chars = command.get(start..)?.char_indices();
+            for _ in 0..n {
+                chars
+                    .next()
+                    .filter(|(_, c)| c.is_alphanumeric() || *c == '_')?;
+            }
+            Some(chars.offset() + start)
+        };
+        let Some((i, mut end)) = (0..bytes.len()).find_map(|i| {
+            if !(is(i..i + 6, u8::is_ascii_uppercase)
+                && bytes[i + 6..].starts_with(b"/")
+                && is(i + 7..i + 9, u8::is_ascii_digit))
+            {
+                return None;
+            }
+            Some((i, words(i + 9, 3)?))
+        }) else {
+            return;
+        };
+        if is(end..end + 2, u8::is_ascii_digit) {
+            end += 2;
+        }
+        fdinfo.org = Some(&command[i..i + 3]);
+        fdinfo.dst = Some(&command[i + 3..i + 6]);
+        fdinfo.query_time = Some(&command[i + 7..end]);
+        fdinfo.airline = match bytes[end..].starts_with(b"/") {
+            true => words(end + 1, 2).map(|x| &command[end + 1..x]),
+            false => None,
+        };
+    }
+}
+
+impl Render for Fd<'_> {
+    /// Render the header and the lines of fare, the command of the header is rendered
+    /// from the query when there is the city pair, otherwise it's the command parsed.
+    fn render(&self) -> String {
+        let mut lines = Vec::new();
+        let command = match (self.org, self.dst, self.query_time) {
+            (Some(org), Some(dst), Some(date)) => {
+                let mut command = format!(":{}{}/{}", org, dst, date);
+                if let Some(airline) = self.airline {
+                    command.push('/');
+                    command.push_str(airline);
+                }
+                Some(command)
+            }
+            _ => self.command.map(str::to_owned),
+        };
+        if let Some(command) = command {
+            let mut header = RowWriter::default();
+            header.push("FD").push(command.trim_end()).push(" ").pad(39);
+            header.push(&format!(
+                "/{}/TPM{:>6}/",
+                self.currency.unwrap_or_default(),
+                self.tpm.unwrap_or_default()
+            ));
+            lines.push(header.finish());
+        }
+        lines.extend(self.items.iter().flatten().map(FdItem::render));
+        lines.join("\n")
+    }
+}
+
+/// The Y prices of the carrier, which the percentage prices such as `50%` are based on.
+struct YPrice<'a> {
+    carrier: Option<&'a str>,
+    ow: Option<f32>,
+    rt: Option<f32>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct FdItem<'a> {
     pub index: Option<u8>,
     pub carrier: Option<&'a str>,
@@ -190,3 +190,69 @@ pub struct FdItem<'a> {
     pub end_date: Option<&'a str>,
     pub policy_no: Option<&'a str>,
 }
+
+impl<'a> FdItem<'a> {
+    /// Parse the line of fare, such as
+    /// `01 KY/J     / 5100.00=10200.00/J /C/  /   .   /25DEC19        /J000  PFN:01`,
+    /// the prices are parsed with the Y prices of the carrier, see [`Fd::parse`].
+    fn parse(line: &'a str, query_time: Option<&'a str>) -> Self {
+        let mut item = FdItem::default();
+        let mut arr = lexer::fields(line, b'/');
+        let index_airline = arr.next();
+        item.index =
+            index_airline.and_then(|x| lexer::trim(util::slice(x, 0, 3)).parse::<u8>().ok());
+        item.carrier = index_airline.map(|x| lexer::trim(util::slice(x, 3, 5)));
+        item.ticket_type = arr.next().map(lexer::trim);
+        if let Some(ow_rt_price) = arr.next() {
+            let mut ow_rt_price = lexer::fields(ow_rt_price, b'=');
+            item.ow_price_raw = ow_rt_price.next().map(lexer::trim);
+            item.rt_price_raw = ow_rt_price.next().map(lexer::trim);
+        }
+        item.cabin = arr.next().map(lexer::trim);
+        item.class = arr.next().map(lexer::trim);
+        arr.next();
+        item.begin_date = arr.next().map(lexer::trim).and_then(|x| match x {
+            "." => query_time,
+            x => Some(x),
+        });
+        item.end_date = arr.next().map(lexer::trim);
+        item.policy_no = arr.next().map(|x| lexer::trim(util::slice(x, 0, 6)));
+        item
+    }
+
+    /// Render the line of fare, such as
+    /// `01 KY/J     / 5100.00=10200.00/J /C/  /   .   /25DEC19        /J000  PFN:01`.
+    fn render(&self) -> String {
+        let index = self.index.map_or(String::new(), |x| format!("{:02}", x));
+        let mut price = format!("{:>8}", self.ow_price_raw.unwrap_or_default());
+        if let Some(rt) = self.rt_price_raw {
+            price.push_str(&format!("={:>8}", rt));
+        }
+        format!(
+            "{:<3}{}/{:<7}/{}/{:<2}/{}/  /{}/{:<14}/{:<6} PFN:{}",
+            index,
+            self.carrier.unwrap_or_default(),
+            self.ticket_type.unwrap_or_default(),
+            price,
+            self.cabin.unwrap_or_default(),
+            self.class.unwrap_or_default(),
+            self.begin_date.unwrap_or_default(),
+            self.end_date.unwrap_or_default(),
+            self.policy_no.unwrap_or_default(),
+            index,
+        )
+    }
+
+    /// Parse the price, the percentage price such as `50%` is of the Y price.
+    fn parse_price(raw: Option<&str>, y: Option<f32>) -> Option<f32> {
+        let raw = raw?;
+        match raw.bytes().any(|b| b == b'%') {
+            true => raw
+                .trim_end_matches('%')
+                .parse::<f32>()
+                .ok()
+                .and_then(|x| y.map(|y| y * x / 100.0)),
+            false => raw.parse::<f32>().ok(),
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::av::{Av, AvFlight};
+use std::collections::HashMap;
+
+/// The minutes of a day.
+const DAY: u32 = 24 * 60;
+
+/// The minimum connecting times, which are looked up by
+/// airport and terminals first, then airport, then the default.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::journey::MinConnectTimes;
+///
+/// let mut mct = MinConnectTimes::new(60);
+/// mct.airport("PVG", 90).terminal("PVG", "T1", "T2", 120);
+/// assert_eq!(mct.get("PVG", Some("T1"), Some("T2")), 120);
+/// assert_eq!(mct.get("PVG", Some("T2"), Some("T2")), 90);
+/// assert_eq!(mct.get("SHA", None, None), 60);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MinConnectTimes {
+    default: u32,
+    airports: HashMap<String, u32>,
+    terminals: HashMap<(String, String, String), u32>,
+}
+
+impl Default for MinConnectTimes {
+    fn default() -> Self {
+        Self::new(60)
+    }
+}
+
+impl MinConnectTimes {
+    /// Create with the default minutes for every airport.
+    pub fn new(default: u32) -> Self {
+        Self {
+            default,
+            airports: HashMap::new(),
+            terminals: HashMap::new(),
+        }
+    }
+
+    /// Set the minutes of an airport.
+    pub fn airport(&mut self, airport: &str, minutes: u32) -> &mut Self {
+        self.airports.insert(airport.to_owned(), minutes);
+        self
+    }
+
+    /// Set the minutes of connecting from the arrival terminal to the departure terminal of an airport.
+    pub fn terminal(
+        &mut self,
+        airport: &str,
+        arr_terminal: &str,
+        dpt_terminal: &str,
+        minutes: u32,
+    ) -> &mut Self {
+        self.terminals.insert(
+            (
+                airport.to_owned(),
+                arr_terminal.to_owned(),
+                dpt_terminal.to_owned(),
+            ),
+            minutes,
+        );
+        self
+    }
+
+    /// Return the minutes of connecting at the airport.
+    pub fn get(
+        &self,
+        airport: &str,
+        arr_terminal: Option<&str>,
+        dpt_terminal: Option<&str>,
+    ) -> u32 {
+        match (arr_terminal, dpt_terminal) {
+            (Some(arr), Some(dpt)) => self
+                .terminals
+                .get(&(airport.to_owned(), arr.to_owned(), dpt.to_owned()))
+                .copied(),
+            _ => None,
+        }
+        .or_else(|| self.airports.get(airport).copied())
+        .unwrap_or(self.default)
+    }
+}
+
+/// The leg of a journey, the times are the minutes since the midnight of the first day of the journey.
+#[derive(Debug)]
+pub struct Leg<'f, 'a> {
+    pub flight: &'f AvFlight<'a>,
+    pub dpt_time: u32,
+    pub arr_time: u32,
+}
+
+impl<'f, 'a> Leg<'f, 'a> {
+    /// Return the minutes in the air.
+    pub fn duration(&self) -> u32 {
+        self.arr_time - self.dpt_time
+    }
+}
+
+/// The connection between two legs of a journey.
+#[derive(Debug, PartialEq)]
+pub struct Connection<'a> {
+    pub airport: &'a str,
+    pub arr_terminal: Option<&'a str>,
+    pub dpt_terminal: Option<&'a str>,
+    /// the minutes between the landing of the inbound leg and the take off of the outbound leg.
+    pub layover: u32,
+    /// the minimum connecting time that the layover is checked against.
+    pub min_connect_time: u32,
+    /// the midnight is passed between the take off of the inbound leg and the take off
+    /// of the outbound leg, in the air or on the ground, such as a red-eye landing at 0020.
+    pub is_overnight: bool,
+    /// the legs are operated by different airlines, see [`AvFlight::operating_airline`].
+    pub is_interline: bool,
+}
+
+impl<'a> Connection<'a> {
+    /// Return whether the layover is not less than the minimum connecting time.
+    pub fn is_valid(&self) -> bool {
+        self.layover >= self.min_connect_time
+    }
+
+    /// Return whether the terminal changes, it's false when any terminal is unknown.
+    pub fn is_terminal_change(&self) -> bool {
+        matches!((self.arr_terminal, self.dpt_terminal), (Some(a), Some(d)) if a != d)
+    }
+}
+
+/// The journey of an option of av, such as a direct flight or a flight with union flights.
+#[derive(Debug)]
+pub struct Journey<'f, 'a> {
+    pub legs: Vec<Leg<'f, 'a>>,
+    pub connections: Vec<Connection<'a>>,
+}
+
+impl<'f, 'a> Journey<'f, 'a> {
+    /// Build the journey of an option of av.
+    ///
+    /// The legs take off on the same day unless the take off time is earlier than
+    /// the landing time of the leg before, then the leg takes off on the next day.
+    pub fn from_flight(flight: &'f AvFlight<'a>, mct: &MinConnectTimes) -> anyhow::Result<Self> {
+        let mut journey = Self {
+            legs: Vec::new(),
+            connections: Vec::new(),
+        };
+        for flight in flight.legs() {
+            let take_off = parse_time(flight.take_off)?;
+            let landing = parse_time(flight.landing)?;
+            let dpt_time = match journey.legs.last() {
+                Some(last) => {
+                    let day = last.arr_time / DAY * DAY;
+                    if day + take_off < last.arr_time {
+                        day + DAY + take_off
+                    } else {
+                        day + take_off
+                    }
+                }
+                None => take_off,
+            };
+            let mut arr_time =
+                dpt_time - take_off + landing + u32::from(flight.landing_addday.unwrap_or(0)) * DAY;
+            if arr_time < dpt_time {
+                arr_time += DAY;
+            }
+            let leg = Leg {
+                flight,
+                dpt_time,
+                arr_time,
+            };
+            if let Some(last) = journey.legs.last() {
+                let arr_terminal = last.flight.arr_terminal;
+                let dpt_terminal = flight.dpt_terminal;
+                journey.connections.push(Connection {
+                    airport: flight.dpt,
+                    arr_terminal,
+                    dpt_terminal,
+                    layover: leg.dpt_time - last.arr_time,
+                    min_connect_time: mct.get(flight.dpt, arr_terminal, dpt_terminal),
+                    is_overnight: leg.dpt_time / DAY > last.dpt_time / DAY,
+                    is_interline: last.flight.operating_airline() != flight.operating_airline(),
+                });
+            }
+            journey.legs.push(leg);
+        }
+        Ok(journey)
+    }
+
+    /// Return whether every connection meets its minimum connecting time.
+    pub fn is_valid(&self) -> bool {
+        self.connections.iter().all(|x| x.is_valid())
+    }
+
+    /// Return whether the journey has no connection.
+    pub fn is_direct(&self) -> bool {
+        self.connections.is_empty()
+    }
+
+    /// Return the minutes from the take off of the first leg to the landing of the last leg.
+    ///
+    /// It's the duration that av shows at the last leg, such as `07:20`, which counts
+    /// the time zones, or the minutes between the local times when it isn't shown.
+    pub fn elapsed(&self) -> u32 {
+        match (self.legs.first(), self.legs.last()) {
+            (Some(first), Some(last)) => last
+                .flight
+                .duration
+                .and_then(parse_duration)
+                .unwrap_or(last.arr_time - first.dpt_time),
+            _ => 0,
+        }
+    }
+
+    /// Return whether any connection is overnight.
+    pub fn is_overnight(&self) -> bool {
+        self.connections.iter().any(|x| x.is_overnight)
+    }
+
+    /// Return whether any connection is interline.
+    pub fn is_interline(&self) -> bool {
+        self.connections.iter().any(|x| x.is_interline)
+    }
+}
+
+impl<'a> Av<'a> {
+    /// Return the journey of every option of the av.
+    pub fn journeys<'f>(&'f self, mct: &MinConnectTimes) -> anyhow::Result<Vec<Journey<'f, 'a>>> {
+        self.flights
+            .iter()
+            .map(|x| Journey::from_flight(x, mct))
+            .collect()
+    }
+}
+
+/// Parse a duration such as `07:20` to the minutes.
+fn parse_duration(text: &str) -> Option<u32> {
+    let (h, m) = text.trim().split_once(':')?;
+    match (h.parse::<u32>(), m.parse::<u32>()) {
+        (Ok(h), Ok(m)) if m < 60 => Some(h * 60 + m),
+        _ => None,
+    }
+}
+
+/// Parse a time such as `0900` to the minutes since midnight.
+fn parse_time(text: &str) -> anyhow::Result<u32> {
+    let time = text.trim();
+    match (time.get(0..2), time.get(2..4), time.len()) {
+        (Some(h), Some(m), 4) => match (h.parse::<u32>(), m.parse::<u32>()) {
+            (Ok(h), Ok(m)) if h < 24 && m < 60 => Ok(h * 60 + m),
+            _ => Err(anyhow::Error::msg(format!("invalid time '{}'.", text))),
+        },
+        _ => Err(anyhow::Error::msg(format!("invalid time '{}'.", text))),
+    }
+}
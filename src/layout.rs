@@ -0,0 +1,217 @@
+use crate::util::{self, slice_columns};
+use std::fmt;
+use std::str::FromStr;
+
+/// The named column of a row, from `start` to `end` in the columns of terminal,
+/// the column without `end` takes the rest of the line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Column {
+    pub name: &'static str,
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl Column {
+    pub const fn new(name: &'static str, start: usize, end: usize) -> Self {
+        Self {
+            name,
+            start,
+            end: Some(end),
+        }
+    }
+
+    pub const fn rest(name: &'static str, start: usize) -> Self {
+        Self {
+            name,
+            start,
+            end: None,
+        }
+    }
+}
+
+/// The layout of a row type of screen, which is described as named columns,
+/// the columns are the fields of `C`, so that a column is read without looking it up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Layout<C: 'static> {
+    pub name: &'static str,
+    /// the version of the columns, which is bumped when they change,
+    /// so that a [`FieldError`] tells the columns that the field is read with.
+    pub version: u16,
+    pub columns: C,
+}
+
+impl<C> Layout<C> {
+    /// Read the line with the columns moved by the offset, the first column is
+    /// widened instead of moved, see [`crate::profile::Profile`].
+    pub fn row_at<'a>(&self, line: &'a str, offset: isize) -> Row<'a> {
+        Row {
+            layout: self.name,
+            version: self.version,
+            line,
+            offset,
+            ascii: line.is_ascii(),
+        }
+    }
+}
+
+/// A line of screen that is written with a layout, the reverse of [`Row`] in the standard profile.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RowWriter {
+    line: String,
+    /// the columns that the line takes on the terminal.
+    width: usize,
+}
+
+impl RowWriter {
+    /// Write the text at the start of the column, the line is padded with spaces up to it.
+    pub fn put(&mut self, column: &Column, text: &str) -> &mut Self {
+        self.pad(column.start);
+        self.push(text)
+    }
+
+    /// Write the text after the text written before.
+    pub fn push(&mut self, text: &str) -> &mut Self {
+        self.line.push_str(text);
+        self.width += util::width(text);
+        self
+    }
+
+    /// Pad the line with spaces up to the column.
+    pub fn pad(&mut self, column: usize) -> &mut Self {
+        while self.width < column {
+            self.line.push(' ');
+            self.width += 1;
+        }
+        self
+    }
+
+    pub fn finish(self) -> String {
+        self.line
+    }
+}
+
+/// A line of screen that is read with a layout.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Row<'a> {
+    /// the name of the layout.
+    pub layout: &'static str,
+    /// the version of the layout.
+    pub version: u16,
+    pub line: &'a str,
+    pub offset: isize,
+    /// the line is ascii, which columns are sliced by bytes directly.
+    pub ascii: bool,
+}
+
+impl<'a> Row<'a> {
+    /// Return the text of the column without trimming, the part beyond the line is cut off,
+    /// so a short line never panics.
+    pub fn raw(&self, column: &Column) -> &'a str {
+        let start = match column.start {
+            0 => 0,
+            start => start.saturating_add_signed(self.offset),
+        };
+        let end = match column.end {
+            Some(end) => end.saturating_add_signed(self.offset),
+            None => usize::MAX,
+        };
+        match self.ascii {
+            true => {
+                let end = end.min(self.line.len());
+                &self.line[start.min(end)..end]
+            }
+            false => slice_columns(self.line, start, end),
+        }
+    }
+
+    /// Return the trimmed text of the column, None when it's blank.
+    pub fn text(&self, column: &Column) -> Option<&'a str> {
+        Some(self.str(column)).filter(|x| !x.is_empty())
+    }
+
+    /// Return the trimmed text of the column, empty when it's blank.
+    pub fn str(&self, column: &Column) -> &'a str {
+        match self.ascii {
+            true => self.raw(column).trim_ascii(),
+            false => self.raw(column).trim(),
+        }
+    }
+
+    /// Return whether the trimmed text of the column equals to the text.
+    pub fn is(&self, column: &Column, text: &str) -> bool {
+        self.str(column) == text
+    }
+
+    /// Parse the trimmed text of the column.
+    pub fn parse<T: FromStr>(&self, column: &Column) -> Result<T, FieldError> {
+        match self.text(column) {
+            Some(text) => text.parse::<T>().map_err(|_| FieldError {
+                layout: self.layout,
+                version: self.version,
+                field: column.name,
+                kind: FieldErrorKind::Invalid(text.to_owned()),
+            }),
+            None => Err(FieldError {
+                layout: self.layout,
+                version: self.version,
+                field: column.name,
+                kind: FieldErrorKind::Missing,
+            }),
+        }
+    }
+}
+
+/// The error of a field that read with a layout, it's returned by the parsers in [`anyhow::Error`].
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::layout::{FieldError, FieldErrorKind};
+///
+/// let text = r" 04AUG(FRI) PKXSHA
+/// 1+  MU5102  DS# JA C8 Y2 BA HA KA LA RQ SQ TQ  PKXSHA 1300   1520   73E 0^B  E  
+///     MU5103  DS# JA C8 Y2 BA HA KA LA RQ SQ TQ     SHA 1300   1520   73E X^B  E  ";
+/// let err = eterm_parser::parse_av(text).unwrap_err();
+/// let err = err.downcast_ref::<FieldError>().unwrap();
+/// assert_eq!(err.field, "stops");
+/// assert_eq!(err.kind, FieldErrorKind::Invalid("X".to_owned()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// the name of the layout, such as `av flight`.
+    pub layout: &'static str,
+    /// the version of the layout.
+    pub version: u16,
+    /// the name of the field, such as `stops`.
+    pub field: &'static str,
+    pub kind: FieldErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldErrorKind {
+    /// the field is blank.
+    Missing,
+    /// the text of the field can't be parsed.
+    Invalid(String),
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            FieldErrorKind::Missing => {
+                write!(
+                    f,
+                    "{} v{} field '{}' is missing.",
+                    self.layout, self.version, self.field
+                )
+            }
+            FieldErrorKind::Invalid(text) => write!(
+                f,
+                "{} v{} field '{}' is invalid: '{}'.",
+                self.layout, self.version, self.field, text
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
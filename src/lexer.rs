@@ -0,0 +1,377 @@
+use crate::normalize::{self, Line};
+
+/// The kind of line of av, which is classified by the leading bytes of the line only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AvKind<'a> {
+    /// the command echoed, such as `>AV:PKXSHA/03AUG`.
+    Prompt,
+    /// the header such as ` 03AUG(THU) PKXSHA VIA KN`, with the date.
+    Header(&'a str),
+    /// the first line of an option such as `1- *KN6856  DS# JA C8`, with the index.
+    Option(u8),
+    /// the first line of a union flight such as `    MU2159  DS# J7 C5`.
+    Union,
+    /// the `>` line such as `>   MU2104      GQ UQ ZQ`.
+    Continuation,
+    /// the line of sub cabins such as `               **M1A Q1S`.
+    SubCabin,
+    Other,
+}
+
+/// The kind of line of fd.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FdKind {
+    /// the header such as `FD:KMGCTU/05SEP23/KY     /CNY /TPM   744/`.
+    Header,
+    /// the line of fare such as `01 KY/J     / 5100.00=10200.00/J /C/  /   .   /25DEC19`.
+    Item,
+    /// the footer such as `PAGE 1/1`, and the blank line.
+    Other,
+}
+
+/// The lines of av that are classified as they're read, see [`av`].
+#[derive(Debug, Clone)]
+pub(crate) struct AvLines<'a> {
+    lines: normalize::Lines<'a>,
+}
+
+impl<'a> Iterator for AvLines<'a> {
+    type Item = (Line<'a>, AvKind<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        Some((line, av_kind(&line)))
+    }
+}
+
+/// Read the lines of av in a single pass, every line is classified as it's read.
+pub(crate) fn av(text: &str) -> AvLines<'_> {
+    AvLines {
+        lines: normalize::lines(text),
+    }
+}
+
+/// Read the lines of screen of fd in a single pass, without the prompts.
+pub(crate) fn fd(text: &str) -> impl Iterator<Item = (&str, FdKind)> {
+    normalize::screen_lines(text).map(|line| (line, fd_kind(line)))
+}
+
+fn av_kind<'a>(line: &Line<'a>) -> AvKind<'a> {
+    let text = line.text;
+    if line.is_prompt() {
+        return AvKind::Prompt;
+    }
+    match text.as_bytes().first() {
+        Some(b'0'..=b'9') => match (header_date(text), option_index(text)) {
+            (Some(date), _) => AvKind::Header(date),
+            (None, Some(index)) => AvKind::Option(index),
+            (None, None) => AvKind::Other,
+        },
+        Some(b'>') => AvKind::Continuation,
+        Some(b' ') => {
+            if let Some(date) = header_date(text) {
+                AvKind::Header(date)
+            } else if is_union_line(text) {
+                AvKind::Union
+            } else if text.trim_start().starts_with("**") {
+                AvKind::SubCabin
+            } else {
+                AvKind::Other
+            }
+        }
+        _ => match header_date(text) {
+            Some(date) => AvKind::Header(date),
+            None if text.trim_start().starts_with("**") => AvKind::SubCabin,
+            None => AvKind::Other,
+        },
+    }
+}
+
+fn fd_kind(line: &str) -> FdKind {
+    if line.starts_with("FD:") {
+        FdKind::Header
+    } else if line.starts_with("PAGE") || line.trim().is_empty() {
+        FdKind::Other
+    } else {
+        FdKind::Item
+    }
+}
+
+/// Return the index of the option that the line starts with, such as 1 of `1-` and 10 of `10+`.
+pub(crate) fn option_index(line: &str) -> Option<u8> {
+    let head = line.get(..3)?;
+    let digits = head.trim_end_matches([' ', '+', '-', '*']);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse::<u8>().ok()
+}
+
+/// Return the date of the header line, such as 03AUG of ` 03AUG(THU) PKXSHA`.
+pub(crate) fn header_date(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let bytes = line.as_bytes();
+    if bytes.len() < 6
+        || !bytes[..2].iter().all(|b| b.is_ascii_digit())
+        || !bytes[2..5].iter().all(|b| b.is_ascii_uppercase())
+    {
+        return None;
+    }
+    let end = line.find('(')?;
+    match end {
+        5 => Some(&line[..5]),
+        7 if bytes[5..7].iter().all(|b| b.is_ascii_digit()) => Some(&line[..7]),
+        _ => None,
+    }
+}
+
+/// Return whether the line is the first line of a union flight, such as `    MU2159  DS# ...`.
+pub(crate) fn is_union_line(line: &str) -> bool {
+    if !line.starts_with(' ') {
+        return false;
+    }
+    let no = line.trim_start();
+    if no.starts_with("**") {
+        return false;
+    }
+    is_flight_no(no.strip_prefix('*').unwrap_or(no))
+}
+
+/// Return whether the text starts with a flight number, such as `MU2159` or `3U8888`.
+pub(crate) fn is_flight_no(text: &str) -> bool {
+    let no = text.as_bytes();
+    no.len() > 2
+        && no[..2]
+            .iter()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+        && no[2].is_ascii_digit()
+}
+
+/// The iterator of the words that are separated by the ascii whitespaces, see [`words`].
+#[derive(Debug, Clone)]
+pub(crate) struct Words<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.text.as_bytes();
+        let mut start = self.offset;
+        while start < bytes.len() && bytes[start].is_ascii_whitespace() {
+            start += 1;
+        }
+        if start == bytes.len() {
+            self.offset = start;
+            return None;
+        }
+        let mut end = start + 1;
+        while end < bytes.len() && !bytes[end].is_ascii_whitespace() {
+            end += 1;
+        }
+        self.offset = end;
+        // the ascii whitespaces are always on the char boundaries.
+        Some(&self.text[start..end])
+    }
+}
+
+/// Split the text by the ascii whitespaces in a single scan, such as the cabins `JA C8 YA`.
+pub(crate) fn words(text: &str) -> Words<'_> {
+    Words { text, offset: 0 }
+}
+
+/// The iterator of the fields that are separated by an ascii byte, see [`fields`].
+#[derive(Debug, Clone)]
+pub(crate) struct Fields<'a> {
+    text: &'a str,
+    offset: Option<usize>,
+    separator: u8,
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.offset?;
+        let bytes = self.text.as_bytes();
+        let mut end = start;
+        while end < bytes.len() && bytes[end] != self.separator {
+            end += 1;
+        }
+        self.offset = Some(end + 1).filter(|_| end < bytes.len());
+        // the ascii separator is always on the char boundaries.
+        Some(&self.text[start..end])
+    }
+}
+
+/// Split the text by the ascii separator as `str::split`, the fields such as `/J /C/`
+/// are so short that a byte loop is faster than searching.
+pub(crate) fn fields(text: &str, separator: u8) -> Fields<'_> {
+    debug_assert!(separator.is_ascii());
+    Fields {
+        text,
+        offset: Some(0),
+        separator,
+    }
+}
+
+/// Trim the whitespaces as `str::trim`, the ascii whitespaces are trimmed by bytes
+/// and the others only when the field starts or ends with a non-ascii char.
+pub(crate) fn trim(text: &str) -> &str {
+    let is_space = |b: &u8| matches!(b, b'\t'..=b'\r' | b' ');
+    let bytes = text.as_bytes();
+    let start = bytes
+        .iter()
+        .position(|b| !is_space(b))
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|b| !is_space(b))
+        .map_or(start, |x| x + 1);
+    let text = &text[start..end];
+    match (text.as_bytes().first(), text.as_bytes().last()) {
+        (Some(a), Some(b)) if !a.is_ascii() || !b.is_ascii() => text.trim(),
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn av_kind_test() {
+        let text = ">AV:PKXSHA/03AUG
+ 03AUG(THU) PKXSHA VIA KN
+1- *KN6856  DS# JA C8 YA BA HA KA LA RQ SQ TQ  PKXXIY 0900   1120   321 0^B  E
+>   MU2104      GQ UQ ZQ                                            -- T3 02:20
+    MU2159  DS# J7 C5 D4 Q2 IQ YA BA MA EA HQ     SHA 1400   1620   32L 0^S  E
+   *MU3502  DS# YA BS MA ES KA LS NA RA SQ VQ     PVG 1500   1720   32S 0^S  E
+               **M1A Q1S V18
+10+ KN5730  DS# WA YA BA MA EA HA KA LA NA R6  PKXWNZ 0915   1145   73U 0^   E
+15SEP23(FRI) BJSNYC
+**M1A
+NO DISPLAY
+PAGE 1/1";
+        let kinds = av(text).map(|(_, kind)| kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                AvKind::Prompt,
+                AvKind::Header("03AUG"),
+                AvKind::Option(1),
+                AvKind::Continuation,
+                AvKind::Union,
+                AvKind::Union,
+                AvKind::SubCabin,
+                AvKind::Option(10),
+                AvKind::Header("15SEP23"),
+                AvKind::SubCabin,
+                AvKind::Other,
+                AvKind::Other,
+            ]
+        );
+    }
+
+    #[test]
+    fn fd_kind_test() {
+        let text = ">FD:KMGCTU
+FD:KMGCTU/05SEP23/KY                   /CNY /TPM   744/
+01 KY/J     / 5100.00=10200.00/J /C/  /   .   /25DEC19        /J000  PFN:01
+                                                                                
+PAGE 1/1       /LPRIC/C52DZF3YARTGI11";
+        let kinds = fd(text).map(|(_, kind)| kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [FdKind::Header, FdKind::Item, FdKind::Other, FdKind::Other]
+        );
+    }
+
+    #[test]
+    fn option_index_test() {
+        assert_eq!(option_index("1- *KN6856"), Some(1));
+        assert_eq!(option_index("2   KN5730"), Some(2));
+        assert_eq!(option_index("4+  KN5730"), Some(4));
+        assert_eq!(option_index("10+ KN5730"), Some(10));
+        assert_eq!(option_index("1"), None);
+        assert_eq!(option_index("   MU2159"), None);
+        assert_eq!(option_index("1A KN5730"), None);
+        assert_eq!(option_index("999KN5730"), None);
+        assert_eq!(option_index("1中"), None);
+    }
+
+    #[test]
+    fn header_date_test() {
+        assert_eq!(header_date(" 03AUG(THU) PKXSHA"), Some("03AUG"));
+        assert_eq!(header_date("15SEP23(FRI) BJSNYC"), Some("15SEP23"));
+        assert_eq!(header_date(" 03AUG PKXSHA"), None);
+        assert_eq!(header_date(" 03aug(THU) PKXSHA"), None);
+        assert_eq!(header_date(" 03AUG2(THU)"), None);
+        assert_eq!(header_date("1- *KN6856"), None);
+        assert_eq!(header_date("03AU"), None);
+        assert_eq!(header_date(" 03中(THU)"), None);
+    }
+
+    #[test]
+    fn is_union_line_test() {
+        assert!(is_union_line("    MU2159  DS# J7"));
+        assert!(is_union_line("   *MU3502  DS# YA"));
+        assert!(is_union_line("    3U8888  DS# YA"));
+        assert!(!is_union_line("1- *KN6856  DS# JA"));
+        assert!(!is_union_line("               **M1A Q1S"));
+        assert!(!is_union_line(">   MU2104      GQ"));
+        assert!(!is_union_line("    MU"));
+        assert!(!is_union_line("    NO DISPLAY"));
+        assert!(!is_union_line(""));
+    }
+
+    #[test]
+    fn words_test() {
+        assert_eq!(
+            words(" JA C8\tYA  ").collect::<Vec<_>>(),
+            ["JA", "C8", "YA"]
+        );
+        assert_eq!(words("石风芸 CHD").collect::<Vec<_>>(), ["石风芸", "CHD"]);
+        assert_eq!(words("").next(), None);
+        assert_eq!(words("   ").next(), None);
+    }
+
+    #[test]
+    fn fields_test() {
+        assert_eq!(
+            fields("/J /C/", b'/').collect::<Vec<_>>(),
+            ["", "J ", "C", ""]
+        );
+        assert_eq!(fields("KY", b'/').collect::<Vec<_>>(), ["KY"]);
+        assert_eq!(fields("", b'/').collect::<Vec<_>>(), [""]);
+        assert_eq!(fields("中/文", b'/').collect::<Vec<_>>(), ["中", "文"]);
+    }
+
+    #[test]
+    fn trim_test() {
+        assert_eq!(trim("  KY \r\n"), "KY");
+        assert_eq!(trim("   "), "");
+        assert_eq!(trim(""), "");
+        assert_eq!(trim("\u{3000}石风芸\u{3000}"), "石风芸");
+        assert_eq!(trim(" 石 风 "), "石 风");
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn words_split_test(text in "[ \tA-Z0-9中]{0,20}") {
+            proptest::prop_assert!(words(&text).eq(text.split_ascii_whitespace()));
+        }
+
+        #[test]
+        fn fields_split_test(text in "[ /A-Z0-9中]{0,20}") {
+            proptest::prop_assert!(fields(&text, b'/').eq(text.split('/')));
+        }
+
+        #[test]
+        fn trim_str_test(text in "[ \t\r\n\u{3000}A-Z中]{0,20}") {
+            proptest::prop_assert_eq!(trim(&text), text.trim());
+        }
+    }
+}
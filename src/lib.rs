@@ -72,19 +72,49 @@
 //!
 //!
 
+mod lexer;
 mod util;
 /// The module include text parser and result type of response of av command.
 pub mod av;
+/// The module include parsing of a batch of screens, in parallel with the `rayon` feature.
+pub mod batch;
+/// The module include builders and parser of the command lines, such as `AV:PEKSHA/03AUG/CA`.
+pub mod command;
 /// The module include text parser and result type of response of detr command.
 pub mod detr;
+/// The module include decoding of the bytes that eterm server response, such as GBK.
+#[cfg(feature = "encoding")]
+pub mod encoding;
+/// The module include recognising of the errors that eterm host replies, such as `NO PNR`.
+pub mod error;
 /// The module include text parser and result type of response of fd command.
 pub mod fd;
+/// The module include journey built from the options of av, with minimum connecting time checks.
+pub mod journey;
+/// The module include the layouts of the columns of the screens, and the error of the field read with them.
+pub mod layout;
 /// The module include text parser and result type of response of ml command.
 pub mod ml;
+/// The module include the mock of eterm server that replies the screens of the fixtures.
+#[cfg(feature = "mock-server")]
+pub mod mock;
+/// The module include normalizing of the screen, such as the newlines, the control codes and the prompts.
+pub mod normalize;
 /// The module include text parser and result type of response of pat command.
 pub mod pat;
 /// The module include text parser and result type of response of rt command.
 pub mod pnr;
+/// The module include layout profiles of the columns of av and ml for different eterm versions.
+pub mod profile;
+/// The module include rendering of the screen text from the results that parsed, such as the fixtures.
+pub mod render;
+/// The module include the response of any command, which kind is detected from the screen.
+pub mod response;
+/// The module include the session of eterm terminal over TCP, with sign-in and paging.
+#[cfg(feature = "session")]
+pub mod session;
+/// The module include assembling of the screens from the bytes that arrive from the socket.
+pub mod stream;
 
 /// Parse av text that eterm server response.
 ///
@@ -116,7 +146,7 @@ pub mod pnr;
 ///     panic!("av parse error");
 /// }
 /// ```
-pub fn parse_av(text: &str) -> anyhow::Result<av::Av> {
+pub fn parse_av(text: &str) -> anyhow::Result<av::Av<'_>> {
     av::Av::parse(text)
 }
 
@@ -146,7 +176,7 @@ pub fn parse_av(text: &str) -> anyhow::Result<av::Av> {
 ///     panic!("detr parse error");
 /// }
 /// ```
-pub fn parse_detr(text: &str) -> anyhow::Result<detr::Detr> {
+pub fn parse_detr(text: &str) -> anyhow::Result<detr::Detr<'_>> {
     detr::Detr::parse(text)
 }
 
@@ -179,7 +209,7 @@ pub fn parse_detr(text: &str) -> anyhow::Result<detr::Detr> {
 ///     panic!("fd parse error");
 /// }
 /// ```
-pub fn parse_fd(text: &str) -> anyhow::Result<fd::Fd> {
+pub fn parse_fd(text: &str) -> anyhow::Result<fd::Fd<'_>> {
     fd::Fd::parse(text)
 }
 
@@ -204,7 +234,7 @@ pub fn parse_fd(text: &str) -> anyhow::Result<fd::Fd> {
 ///     panic!("ml parse error");
 /// }
 /// ```
-pub fn parse_ml(text: &str) -> anyhow::Result<ml::Ml> {
+pub fn parse_ml(text: &str) -> anyhow::Result<ml::Ml<'_>> {
     ml::Ml::parse(text)
 }
 
@@ -234,7 +264,7 @@ pub fn parse_ml(text: &str) -> anyhow::Result<ml::Ml> {
 ///     panic!("pat parse error");
 /// }
 /// ```
-pub fn parse_pat(text: &str) -> anyhow::Result<pat::Pat> {
+pub fn parse_pat(text: &str) -> anyhow::Result<pat::Pat<'_>> {
     pat::Pat::parse(text)
 }
 
@@ -261,6 +291,92 @@ pub fn parse_pat(text: &str) -> anyhow::Result<pat::Pat> {
 ///     panic!("pnr parse error");
 /// }
 /// ```
-pub fn parse_pnr(text: &str) -> anyhow::Result<pnr::Pnr> {
+pub fn parse_pnr(text: &str) -> anyhow::Result<pnr::Pnr<'_>> {
     pnr::Pnr::parse(text)
-}
\ No newline at end of file
+}
+
+/// Parse the text that eterm server response, the command is detected from the text.
+///
+/// # Examples
+///
+/// ```
+/// let text = r">PAT:A
+/// 01 T FARE:CNY520.00 TAX:CNY50.00 YQ:CNY110.00  TOTAL:680.00";
+/// match eterm_parser::parse(text) {
+///     Ok(eterm_parser::response::Response::Pat(pat)) => assert!(pat.items.is_some()),
+///     _ => panic!("pat parse error"),
+/// }
+/// ```
+pub fn parse(text: &str) -> anyhow::Result<response::Response<'_>> {
+    response::Response::parse(text)
+}
+
+/// Parse the texts that eterm server response, see [`batch::parse_batch`].
+pub fn parse_batch<'a>(texts: &[&'a str]) -> Vec<anyhow::Result<response::Response<'a>>> {
+    batch::parse_batch(texts)
+}
+
+/// Parse av bytes that eterm server response, which are decoded into the buffer
+/// unless they are UTF-8 already.
+///
+/// # Examples
+///
+/// ```
+/// let bytes = b" 03AUG(THU) PKXSHA\n1- *KN6856  DS# JA C8 YA BA HA KA LA RQ SQ TQ  PKXXIY 0900   1120   321 0^B  E  ";
+/// let mut buf = String::new();
+/// let av = eterm_parser::parse_av_bytes(bytes, &mut buf).unwrap();
+/// assert_eq!(av.encoding, eterm_parser::encoding::Encoding::Utf8);
+/// assert_eq!(av.value.dpt, Some("PKX"));
+/// ```
+#[cfg(feature = "encoding")]
+pub fn parse_av_bytes<'a>(
+    bytes: &'a [u8],
+    buf: &'a mut String,
+) -> anyhow::Result<encoding::Decoded<av::Av<'a>>> {
+    encoding::parse(bytes, buf, av::Av::parse)
+}
+
+/// Parse detr bytes that eterm server response, see [`parse_av_bytes`].
+#[cfg(feature = "encoding")]
+pub fn parse_detr_bytes<'a>(
+    bytes: &'a [u8],
+    buf: &'a mut String,
+) -> anyhow::Result<encoding::Decoded<detr::Detr<'a>>> {
+    encoding::parse(bytes, buf, detr::Detr::parse)
+}
+
+/// Parse fd bytes that eterm server response, see [`parse_av_bytes`].
+#[cfg(feature = "encoding")]
+pub fn parse_fd_bytes<'a>(
+    bytes: &'a [u8],
+    buf: &'a mut String,
+) -> anyhow::Result<encoding::Decoded<fd::Fd<'a>>> {
+    encoding::parse(bytes, buf, fd::Fd::parse)
+}
+
+/// Parse ml bytes that eterm server response, see [`parse_av_bytes`].
+#[cfg(feature = "encoding")]
+pub fn parse_ml_bytes<'a>(
+    bytes: &'a [u8],
+    buf: &'a mut String,
+) -> anyhow::Result<encoding::Decoded<ml::Ml<'a>>> {
+    encoding::parse(bytes, buf, ml::Ml::parse)
+}
+
+/// Parse pat bytes that eterm server response, see [`parse_av_bytes`].
+#[cfg(feature = "encoding")]
+pub fn parse_pat_bytes<'a>(
+    bytes: &'a [u8],
+    buf: &'a mut String,
+) -> anyhow::Result<encoding::Decoded<pat::Pat<'a>>> {
+    encoding::parse(bytes, buf, pat::Pat::parse)
+}
+
+/// Parse pnr bytes that eterm server response, see [`parse_av_bytes`].
+#[cfg(feature = "encoding")]
+pub fn parse_pnr_bytes<'a>(
+    bytes: &'a [u8],
+    buf: &'a mut String,
+) -> anyhow::Result<encoding::Decoded<pnr::Pnr<'a>>> {
+    encoding::parse(bytes, buf, pnr::Pnr::parse)
+}
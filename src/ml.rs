@@ -1,4 +1,43 @@
-#[derive(Default, Debug)]
+use crate::error;
+use crate::layout::{Column, Layout, RowWriter};
+use crate::normalize;
+use crate::profile::Profile;
+use crate::render::Render;
+
+/// The columns of the line of passenger, such as
+/// ` 001   0DILIAYIAILI      HP3M9L T HX1  VVV211 07SEP      K    T`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PassengerColumns {
+    index: Column,
+    group_count: Column,
+    passenger_name: Column,
+    pnr_code: Column,
+    flight_class: Column,
+    action_code: Column,
+    seat_count: Column,
+    office_code: Column,
+    created_date: Column,
+    passenger_info: Column,
+}
+
+const PASSENGER_LAYOUT: Layout<PassengerColumns> = Layout {
+    name: "ml passenger",
+    version: 1,
+    columns: PassengerColumns {
+        index: Column::new("index", 0, 4),
+        group_count: Column::new("group_count", 7, 8),
+        passenger_name: Column::new("passenger_name", 8, 25),
+        pnr_code: Column::new("pnr_code", 25, 31),
+        flight_class: Column::new("flight_class", 31, 33),
+        action_code: Column::new("action_code", 34, 36),
+        seat_count: Column::new("seat_count", 36, 39),
+        office_code: Column::new("office_code", 39, 45),
+        created_date: Column::new("created_date", 46, 53),
+        passenger_info: Column::rest("passenger_info", 57),
+    },
+};
+
+#[derive(Default, Debug, PartialEq)]
 pub struct Ml<'a> {
     pub raw_text: &'a str,
     pub segs: Option<Vec<MlSeg<'a>>>,
@@ -8,84 +47,245 @@ pub struct Ml<'a> {
 }
 
 impl<'a> Ml<'a> {
+    /// Parse the text with the profile detected from the text.
     pub fn parse(text: &'a str) -> anyhow::Result<Self> {
+        Self::parse_with_profile(text, Profile::detect(text))
+    }
+
+    /// Parse the text with the columns of the profile.
+    pub fn parse_with_profile(text: &'a str, profile: &Profile) -> anyhow::Result<Self> {
         if text.is_empty() {
             return Err(anyhow::Error::msg(
                 "ml parameter shouldn't be empty.".to_owned(),
             ));
         }
+        error::check(text)?;
         let mut mlinfo = Self {
             raw_text: text,
             ..Default::default()
         };
-        let mut lines = text.lines();
-        if let Some(first_line) = lines.next() {
-            if first_line.trim() == "NO-OP" {
-                return Ok(mlinfo);
-            } else if first_line.trim() != "MULTI" {
+        let mut lines = MlLines::new(text, profile);
+        for line in lines.by_ref() {
+            match line? {
+                MlLine::Seg(org, dst) => {
+                    let segment = MlSeg {
+                        org,
+                        dst,
+                        ..Default::default()
+                    };
+                    mlinfo.segs.get_or_insert(Vec::new()).push(segment);
+                }
+                MlLine::Passenger(passenger) => {
+                    if let Some(seg) = mlinfo.segs.as_mut().and_then(|x| x.last_mut()) {
+                        seg.passengers.get_or_insert(Vec::new()).push(passenger);
+                    }
+                }
+            }
+        }
+        mlinfo.flight_no = lines.flight_no;
+        mlinfo.flight_date = lines.flight_date;
+        mlinfo.criteria = lines.criteria;
+        Ok(mlinfo)
+    }
+
+    /// Return the passengers of the text, which are parsed one line at a time as they're asked for,
+    /// so that a long list of passengers can be scanned without collecting it.
+    /// It stops after the error that [`Ml::parse`] fails with.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eterm_parser::ml::Ml;
+    ///
+    /// let text = r"MULTI
+    /// 8L9681 /08SEP          C
+    /// URCKHG
+    ///  001   0DILIAYIAILI      HP3M9L T HX1  VVV211 07SEP      K    T
+    ///  002   1MEIHEREYIABULAI+ KYAH8R T RR1  VVV211 07SEP      K O ST";
+    /// let mut passengers = Ml::passengers_iter(text);
+    /// let passenger = passengers.find_map(|x| x.ok().filter(|x| x.pnr_code == "KYAH8R"));
+    /// assert_eq!(passenger.unwrap().index, Some(2));
+    /// assert_eq!(passengers.seg(), Some(("URC", "KHG")));
+    /// ```
+    pub fn passengers_iter(text: &'a str) -> MlPassengers<'a> {
+        MlPassengers {
+            lines: MlLines::new(text, Profile::detect(text)),
+        }
+    }
+}
+
+impl Render for Ml<'_> {
+    /// Render the header and the passengers in the columns of the standard profile,
+    /// the segment without passengers is followed by `NIL`.
+    fn render(&self) -> String {
+        let mut header = RowWriter::default();
+        for x in [self.flight_no, self.flight_date].into_iter().flatten() {
+            header.push(x).push(" ");
+        }
+        if let Some(criteria) = self.criteria {
+            header.pad(23).push(criteria);
+        }
+        let mut lines = vec!["MULTI".to_owned(), header.finish().trim_end().to_owned()];
+        for seg in self.segs.iter().flatten() {
+            lines.push(format!("{}{}", seg.org, seg.dst));
+            match &seg.passengers {
+                Some(passengers) => lines.extend(passengers.iter().map(MlPassenger::render)),
+                None => lines.push("NIL".to_owned()),
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// The iterator of the passengers of ml, see [`Ml::passengers_iter`].
+#[derive(Debug, Clone)]
+pub struct MlPassengers<'a> {
+    lines: MlLines<'a>,
+}
+
+impl<'a> MlPassengers<'a> {
+    /// Return the segment of the passenger that is returned last, such as URC and KHG of `URCKHG`.
+    pub fn seg(&self) -> Option<(&'a str, &'a str)> {
+        self.lines.seg
+    }
+}
+
+impl<'a> Iterator for MlPassengers<'a> {
+    type Item = anyhow::Result<MlPassenger<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lines.find_map(|line| match line {
+            Ok(MlLine::Seg(..)) => None,
+            Ok(MlLine::Passenger(passenger)) => Some(Ok(passenger)),
+            Err(e) => Some(Err(e)),
+        })
+    }
+}
+
+/// The line of ml, the segment or the passenger of it.
+enum MlLine<'a> {
+    Seg(&'a str, &'a str),
+    Passenger(MlPassenger<'a>),
+}
+
+/// The lines of ml that are parsed one at a time, the header is taken on the way.
+#[derive(Debug, Clone)]
+struct MlLines<'a> {
+    lines: normalize::Lines<'a>,
+    profile: Profile,
+    /// the lines are read before the header, such as `MULTI`.
+    header: bool,
+    flight_no: Option<&'a str>,
+    flight_date: Option<&'a str>,
+    criteria: Option<&'a str>,
+    seg: Option<(&'a str, &'a str)>,
+    done: bool,
+}
+
+impl<'a> MlLines<'a> {
+    fn new(text: &'a str, profile: &Profile) -> Self {
+        Self {
+            lines: normalize::lines(text),
+            profile: *profile,
+            header: false,
+            flight_no: None,
+            flight_date: None,
+            criteria: None,
+            seg: None,
+            done: false,
+        }
+    }
+
+    fn screen_line(&mut self) -> Option<&'a str> {
+        self.lines.by_ref().find(|x| !x.is_prompt()).map(|x| x.text)
+    }
+
+    /// Read the header, such as `MULTI` and `8L9681 /08SEP          C`.
+    fn parse_header(&mut self) -> anyhow::Result<()> {
+        match self.screen_line().map(|x| x.trim()) {
+            Some("MULTI") => {}
+            Some("NO-OP") | None => {
+                self.done = true;
+                return Ok(());
+            }
+            Some(_) => {
+                self.done = true;
                 return Err(anyhow::Error::msg("ml must start with 'MULTI'.".to_owned()));
             }
-        } else {
-            return Ok(mlinfo);
         }
+        match self.screen_line() {
+            Some(second_line) => {
+                let mut info = second_line.split(&[' ']).filter(|x| !x.is_empty());
+                self.flight_no = info.next();
+                self.flight_date = info.next();
+                self.criteria = info.next();
+            }
+            None => self.done = true,
+        }
+        Ok(())
+    }
 
-        if let Some(second_line) = lines.next() {
-            let mut info = second_line.split(&[' ']).filter(|x|!x.is_empty());
-            mlinfo.flight_no = info.next().and_then(|x| Some(x));
-            mlinfo.flight_date = info.next().and_then(|x| Some(x));
-            mlinfo.criteria = info.next().and_then(|x| Some(x));
-        } else {
-            return Ok(mlinfo);
+    fn parse_passenger(&self, line: &'a str) -> MlPassenger<'a> {
+        let row = PASSENGER_LAYOUT.row_at(line, self.profile.ml_offset);
+        let c = &PASSENGER_LAYOUT.columns;
+        MlPassenger {
+            raw_text: line,
+            index: row.parse::<u8>(&c.index).ok(),
+            group_count: row.parse::<u8>(&c.group_count).ok(),
+            passenger_name: row.str(&c.passenger_name),
+            pnr_code: row.str(&c.pnr_code),
+            flight_class: row.str(&c.flight_class),
+            action_code: row.str(&c.action_code),
+            seat_count: row.parse::<u8>(&c.seat_count).ok(),
+            office_code: row.str(&c.office_code),
+            created_date: row.str(&c.created_date),
+            passenger_info: row.str(&c.passenger_info),
         }
-        for line in lines {
-            let line = line.trim_end_matches(&['+', '-']);
-
-            if line.trim().len() == 6 {
-                let segment = MlSeg {
-                    org: &line[0..3],
-                    dst: &line[3..],
-                    ..Default::default()
-                };
-                mlinfo.segs.get_or_insert(Vec::new()).push(segment);
-            } else if regex::Regex::is_match(&regex::Regex::new(r"^\s*\d+")?, line) {
-                if let Some(ref mut segs) = &mut mlinfo.segs.as_mut() {
-                    if let Some(seg) = segs.last_mut() {
-                        let passenger = MlPassenger {
-                            raw_text: line,
-                            index: line[0..4].parse::<u8>().ok(),
-                            group_count: line[7..8].parse::<u8>().ok(),
-                            passenger_name: &line[8..25],
-                            pnr_code: &line[25..31],
-                            flight_class: &line[31..33],
-                            action_code: &line[34..36],
-                            seat_count: line[36..39].parse::<u8>().ok(),
-                            office_code: &line[40..46],
-                            created_date: &line[47..54],
-                            passenger_info: &line[60..],
-                        };
-                        if let Some(passengers) = &mut seg.passengers {
-                            passengers.push(passenger);
-                        }
-                    }
-                } else {
-                    return Err(anyhow::Error::msg(
+    }
+}
+
+impl<'a> Iterator for MlLines<'a> {
+    type Item = anyhow::Result<MlLine<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.header {
+            self.header = true;
+            if let Err(e) = self.parse_header() {
+                return Some(Err(e));
+            }
+        }
+        while !self.done {
+            let Some(line) = self.screen_line() else {
+                self.done = true;
+                break;
+            };
+            let line = line.trim_end_matches(['+', '-']);
+            if line.trim().len() == 6 && line.is_ascii() {
+                let line = line.trim();
+                self.seg = Some((&line[0..3], &line[3..]));
+                return Some(Ok(MlLine::Seg(&line[0..3], &line[3..])));
+            } else if line.trim_start().starts_with(|c: char| c.is_ascii_digit()) {
+                if self.seg.is_none() {
+                    self.done = true;
+                    return Some(Err(anyhow::Error::msg(
                         "parse failed,passenger must has segment.".to_owned(),
-                    ));
+                    )));
                 }
+                return Some(Ok(MlLine::Passenger(self.parse_passenger(line))));
             }
         }
-        Ok(mlinfo)
+        None
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct MlSeg<'a> {
     pub org: &'a str,
     pub dst: &'a str,
     pub passengers: Option<Vec<MlPassenger<'a>>>,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct MlPassenger<'a> {
     pub index: Option<u8>,
     pub group_count: Option<u8>,
@@ -99,3 +299,26 @@ pub struct MlPassenger<'a> {
     pub passenger_info: &'a str,
     pub raw_text: &'a str,
 }
+
+impl MlPassenger<'_> {
+    /// Render the line of passenger, such as
+    /// ` 001   0DILIAYIAILI      HP3M9L T HX1  VVV211 07SEP      K    T`.
+    fn render(&self) -> String {
+        let c = &PASSENGER_LAYOUT.columns;
+        let number = |x: Option<u8>, width: usize| {
+            x.map_or(String::new(), |x| format!("{:0width$}", x, width = width))
+        };
+        let mut row = RowWriter::default();
+        row.put(&c.index, &format!("{:>4}", number(self.index, 3)))
+            .put(&c.group_count, &number(self.group_count, 1))
+            .put(&c.passenger_name, self.passenger_name)
+            .put(&c.pnr_code, self.pnr_code)
+            .put(&c.flight_class, &format!(" {}", self.flight_class))
+            .put(&c.action_code, self.action_code)
+            .put(&c.seat_count, &number(self.seat_count, 1))
+            .put(&c.office_code, self.office_code)
+            .put(&c.created_date, self.created_date)
+            .put(&c.passenger_info, self.passenger_info);
+        row.finish().trim_end().to_owned()
+    }
+}
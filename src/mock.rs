@@ -0,0 +1,368 @@
+use crate::normalize;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The byte that ends a screen, see [`crate::stream::ScreenAssembler`].
+const ETX: u8 = 0x03;
+
+/// The screen that the server replies with the command that no fixture matches.
+pub const FORMAT_SCREEN: &str = "FORMAT";
+
+/// The screen that the server replies with `PN` at the last page, or `PB` at the first page.
+pub const NO_PAGE_SCREEN: &str = "NO DISPLAY";
+
+/// The script that returns the pages of the command, see [`MockServer::script`].
+pub type Script = Arc<dyn Fn(&str) -> Vec<String> + Send + Sync>;
+
+/// The pages that the command is replied with.
+#[derive(Clone)]
+enum Reply {
+    Pages(Vec<String>),
+    Script(Script),
+}
+
+impl std::fmt::Debug for Reply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pages(pages) => f.debug_tuple("Pages").field(pages).finish(),
+            Self::Script(_) => f.write_str("Script"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    pattern: String,
+    reply: Reply,
+}
+
+/// The mock of eterm server that replies the commands with the screens of the fixtures,
+/// so that the clients can be tested without the host.
+///
+/// A command is a line that ends with `\r` or `\n`, the SOE and `>` before it are ignored.
+/// The screen is replied after the command echoed such as `>RT KE9SWE`, and ends with `\x03`.
+/// The command is matched against the patterns in the order that they're added,
+/// `*` in the pattern matches any text and `?` matches a char, such as `AV:PEK*`.
+///
+/// The screen of many pages is shown one page at a time, `PN`, `PB`, `PF` and `PL`
+/// show the next, the previous, the first and the last page of it,
+/// every page but the last ends with `+` as eterm shows.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::mock::MockServer;
+/// use eterm_parser::stream::ScreenReader;
+/// use std::io::Write;
+///
+/// let mut server = MockServer::new();
+/// server.reply_pages("RT *", [" 1.ZHANGSAN KE9SWE", " 2.KMG123"]);
+/// let handle = server.bind("127.0.0.1:0").unwrap();
+///
+/// let mut stream = std::net::TcpStream::connect(handle.local_addr()).unwrap();
+/// stream.write_all(b"RT KE9SWE\rPN\r").unwrap();
+/// let mut screens = ScreenReader::new(stream);
+/// let screen = screens.next().unwrap().unwrap();
+/// assert_eq!(screen.text, ">RT KE9SWE\r\n 1.ZHANGSAN KE9SWE+");
+/// assert!(screen.has_more);
+/// assert_eq!(screens.next().unwrap().unwrap().text, ">PN\r\n 2.KMG123");
+/// ```
+#[derive(Debug, Clone)]
+pub struct MockServer {
+    routes: Vec<Route>,
+    fallback: String,
+    latency: Duration,
+}
+
+impl Default for MockServer {
+    fn default() -> Self {
+        Self {
+            routes: Vec::new(),
+            fallback: FORMAT_SCREEN.to_owned(),
+            latency: Duration::ZERO,
+        }
+    }
+}
+
+impl MockServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reply the commands that match the pattern with the screen,
+    /// such as an error screen like `NO PNR`.
+    pub fn reply(&mut self, pattern: &str, screen: &str) -> &mut Self {
+        self.reply_pages(pattern, [screen])
+    }
+
+    /// Reply the commands that match the pattern with the pages of screen.
+    pub fn reply_pages<I, S>(&mut self, pattern: &str, pages: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let pages = pages.into_iter().map(Into::into).collect();
+        self.route(pattern, Reply::Pages(pages))
+    }
+
+    /// Reply the commands that match the pattern with the pages that the script returns,
+    /// the script is called with the command.
+    pub fn script<F>(&mut self, pattern: &str, script: F) -> &mut Self
+    where
+        F: Fn(&str) -> Vec<String> + Send + Sync + 'static,
+    {
+        self.route(pattern, Reply::Script(Arc::new(script)))
+    }
+
+    /// Set the screen that the command that no pattern matches is replied with,
+    /// the default is [`FORMAT_SCREEN`].
+    pub fn fallback(&mut self, screen: &str) -> &mut Self {
+        self.fallback = screen.to_owned();
+        self
+    }
+
+    /// Set the delay before every screen is replied.
+    pub fn latency(&mut self, latency: Duration) -> &mut Self {
+        self.latency = latency;
+        self
+    }
+
+    fn route(&mut self, pattern: &str, reply: Reply) -> &mut Self {
+        self.routes.push(Route {
+            pattern: pattern.trim().to_uppercase(),
+            reply,
+        });
+        self
+    }
+
+    /// Add the screens of the fixture, which is the text of a session such as
+    ///
+    /// ```text
+    /// >RT KE9SWE
+    ///  1.ZHANGSAN KE9SWE
+    /// >PN
+    ///  2.KMG123
+    /// ```
+    ///
+    /// the prompt is the pattern of command, the pages of its screen follow it and are separated by `>PN`.
+    pub fn load_fixture(&mut self, text: &str) -> anyhow::Result<&mut Self> {
+        let mut pattern = None;
+        let mut pages: Vec<Vec<&str>> = Vec::new();
+        for line in text.lines() {
+            let prompt = normalize::lines(line)
+                .next()
+                .filter(|x| x.is_prompt())
+                .map(|x| x.text);
+            match (prompt, &pattern) {
+                (Some(x), Some(_)) if x.eq_ignore_ascii_case("PN") => pages.push(Vec::new()),
+                (Some(x), _) => {
+                    if let Some(pattern) = pattern.replace(x) {
+                        self.reply_pages(pattern, pages.drain(..).map(|x| x.join("\r\n")));
+                    }
+                    pages.push(Vec::new());
+                }
+                (None, Some(_)) => pages.last_mut().into_iter().for_each(|x| x.push(line)),
+                (None, None) if line.trim().is_empty() => {}
+                (None, None) => {
+                    return Err(anyhow::Error::msg(format!(
+                        "fixture must start with the prompt of command: {}",
+                        line
+                    )))
+                }
+            }
+        }
+        if let Some(pattern) = pattern {
+            self.reply_pages(pattern, pages.drain(..).map(|x| x.join("\r\n")));
+        }
+        Ok(self)
+    }
+
+    /// Add the fixtures of the files in the directory, in the order of the file names.
+    pub fn load_fixtures(&mut self, dir: impl AsRef<Path>) -> anyhow::Result<&mut Self> {
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|x| x.map(|x| x.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+        for path in paths.iter().filter(|x| x.is_file()) {
+            let text = std::fs::read_to_string(path)?;
+            self.load_fixture(&text)
+                .map_err(|e| anyhow::Error::msg(format!("{}: {}", path.display(), e)))?;
+        }
+        Ok(self)
+    }
+
+    /// Return the pages of the command, the fallback when no pattern matches.
+    fn pages(&self, command: &str) -> Vec<String> {
+        let command = command.to_uppercase();
+        let reply = self
+            .routes
+            .iter()
+            .find(|x| matches(&x.pattern, &command))
+            .map(|x| &x.reply);
+        match reply {
+            Some(Reply::Pages(pages)) => pages.clone(),
+            Some(Reply::Script(script)) => script(&command),
+            None => vec![self.fallback.clone()],
+        }
+    }
+
+    /// Listen on the address and serve the connections in the background,
+    /// the server stops when the handle is dropped.
+    pub fn bind(&self, addr: impl ToSocketAddrs) -> io::Result<MockHandle> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let server = self.clone();
+        let thread = {
+            let stopped = stopped.clone();
+            thread::spawn(move || server.serve_until(listener, &stopped))
+        };
+        Ok(MockHandle {
+            addr,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    /// Serve the connections of the listener, every connection in a thread.
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        self.serve_until(listener, &AtomicBool::new(false))
+    }
+
+    fn serve_until(&self, listener: TcpListener, stopped: &AtomicBool) -> io::Result<()> {
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            // the failed accept such as `ECONNABORTED` or `EMFILE` is of a connection,
+            // the server keeps serving the others.
+            let Ok(stream) = stream else {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            };
+            let server = self.clone();
+            thread::spawn(move || server.handle(stream));
+        }
+        Ok(())
+    }
+
+    /// Reply the commands of the connection until it's closed.
+    fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        let mut session = Session::default();
+        let mut buf = Vec::new();
+        let mut chunk = [0; 1024];
+        loop {
+            let n = stream.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(());
+            }
+            buf.extend_from_slice(&chunk[..n]);
+            while let Some(i) = buf.iter().position(|x| matches!(x, b'\r' | b'\n')) {
+                let line = buf.drain(..=i).collect::<Vec<_>>();
+                let line = String::from_utf8_lossy(&line);
+                let Some(command) = normalize::lines(&line).next().map(|x| x.text.trim()) else {
+                    continue;
+                };
+                if command.is_empty() {
+                    continue;
+                }
+                let screen = session.screen(self, command);
+                thread::sleep(self.latency);
+                stream.write_all(format!(">{}\r\n{}", command, screen).as_bytes())?;
+                stream.write_all(&[ETX])?;
+            }
+        }
+    }
+}
+
+/// The pages of the screen that the connection shows.
+#[derive(Debug, Default)]
+struct Session {
+    pages: Vec<String>,
+    page: usize,
+}
+
+impl Session {
+    /// Return the screen of the command, the page of the screen shown for the paging commands.
+    fn screen(&mut self, server: &MockServer, command: &str) -> String {
+        let page = match command.to_uppercase().as_str() {
+            "PN" => self.page + 1,
+            "PB" => self.page.wrapping_sub(1),
+            "PF" => 0,
+            "PL" => self.pages.len().saturating_sub(1),
+            _ => {
+                self.pages = server.pages(command);
+                0
+            }
+        };
+        let Some(screen) = self.pages.get(page) else {
+            return NO_PAGE_SCREEN.to_owned();
+        };
+        self.page = page;
+        match page + 1 < self.pages.len() && !screen.trim_end().ends_with('+') {
+            true => format!("{}+", screen.trim_end()),
+            false => screen.to_owned(),
+        }
+    }
+}
+
+/// Return whether the command matches the pattern, `*` matches any text and `?` matches a char.
+fn matches(pattern: &str, command: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let command = command.trim().chars().collect::<Vec<_>>();
+    let (mut p, mut c) = (0, 0);
+    // the position of the last `*` and the command that it matches up to.
+    let mut star = None;
+    while c < command.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, c));
+                p += 1;
+            }
+            Some(x) if *x == '?' || *x == command[c] => {
+                p += 1;
+                c += 1;
+            }
+            _ => match star {
+                Some((sp, sc)) => {
+                    star = Some((sp, sc + 1));
+                    p = sp + 1;
+                    c = sc + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|x| *x == '*')
+}
+
+/// The handle of the server that serves in the background, see [`MockServer::bind`].
+#[derive(Debug)]
+pub struct MockHandle {
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl MockHandle {
+    /// Return the address that the server listens on, such as the port that the system assigned.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for MockHandle {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // the listener is waiting for a connection, it's woken up to see the stop.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
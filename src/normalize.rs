@@ -0,0 +1,215 @@
+use std::borrow::Cow;
+
+/// The marks of the start of entry that eterm shows before the command, such as `►`.
+const SOE: [char; 2] = ['►', '\u{1E}'];
+
+/// The kind of line of screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// the line that eterm server responses.
+    Screen,
+    /// the command echoed, such as `>AV:PKXSHA/03AUG` and `►RT KE9SWE`.
+    Prompt,
+}
+
+/// The line of screen that is normalized without copying.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line<'a> {
+    /// the text without the newline, the padding and the control codes at both ends,
+    /// the prompt is without the SOE and `>` either.
+    pub text: &'a str,
+    /// the offset of the text in the original text.
+    pub offset: usize,
+    pub kind: LineKind,
+}
+
+impl<'a> Line<'a> {
+    pub fn is_prompt(&self) -> bool {
+        self.kind == LineKind::Prompt
+    }
+}
+
+/// The iterator of the normalized lines, see [`lines`].
+#[derive(Debug, Clone)]
+pub struct Lines<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.text.len() {
+            return None;
+        }
+        let start = self.offset;
+        let rest = &self.text[start..];
+        // the `\r` is looked for before the `\n` only, both are found by memchr.
+        let n = rest.find('\n').unwrap_or(rest.len());
+        let (raw, next) = match rest[..n].find('\r') {
+            Some(r) if r + 1 == n => (&rest[..r], n + 1),
+            Some(r) => (&rest[..r], r + 1),
+            None => (&rest[..n], (n + 1).min(rest.len())),
+        };
+        self.offset += next;
+        Some(normalize_line(raw, start))
+    }
+}
+
+/// Return the normalized lines of the text, the `\r\n`, `\n` and `\r` are all newlines,
+/// the control codes in the middle of line are kept, the parsers treat them as spaces.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::normalize::{lines, LineKind};
+///
+/// let text = "►>AV:PKXSHA/03AUG\r\n 03AUG(THU) PKXSHA   \x1D\r\n>   MU2104      GQ UQ ZQ";
+/// let lines = lines(text).collect::<Vec<_>>();
+/// assert_eq!(lines[0].text, "AV:PKXSHA/03AUG");
+/// assert_eq!(lines[0].kind, LineKind::Prompt);
+/// assert_eq!(lines[1].text, " 03AUG(THU) PKXSHA");
+/// assert_eq!(lines[1].offset, 21);
+/// assert_eq!(lines[2].kind, LineKind::Screen);
+/// ```
+pub fn lines(text: &str) -> Lines<'_> {
+    Lines { text, offset: 0 }
+}
+
+/// Return the lines of screen, without the prompts.
+pub fn screen_lines(text: &str) -> impl Iterator<Item = &str> {
+    lines(text).filter(|x| !x.is_prompt()).map(|x| x.text)
+}
+
+/// Return the text from the first line of screen to the newline of the last line of screen,
+/// the prompts and the blank lines around are cut off.
+pub fn body(text: &str) -> &str {
+    let mut range: Option<(usize, usize)> = None;
+    for line in lines(text) {
+        if line.is_prompt() || line.text.trim().is_empty() {
+            continue;
+        }
+        let end = line.offset + line.text.len();
+        let end = text[end..].find('\n').map_or(text.len(), |n| end + n + 1);
+        range = Some(range.map_or((line.offset, end), |(start, _)| (start, end)));
+    }
+    range.map_or("", |(start, end)| &text[start..end])
+}
+
+fn normalize_line(raw: &str, offset: usize) -> Line<'_> {
+    // the most lines of screen start with a printable ascii, and end with ascii padding.
+    if let Some(b' ' | b'!'..=b'=' | b'?'..=b'~') = raw.as_bytes().first() {
+        let text = trim_end(raw);
+        return Line {
+            text,
+            offset,
+            kind: LineKind::Screen,
+        };
+    }
+    let text = raw.trim_start_matches(|c: char| c.is_control() && !SOE.contains(&c));
+    let command = text.trim_start_matches(SOE);
+    let mut kind = match command.len() < text.len() {
+        true => LineKind::Prompt,
+        false => LineKind::Screen,
+    };
+    let text = match command.strip_prefix('>') {
+        // the `>` line of av such as `>   MU2104` is a line of screen.
+        Some(x) if !x.starts_with(' ') => {
+            kind = LineKind::Prompt;
+            x.trim_start()
+        }
+        _ if kind == LineKind::Prompt => command.trim_start(),
+        _ => text,
+    };
+    let text = trim_end(text);
+    Line {
+        text,
+        offset: offset + (text.as_ptr() as usize - raw.as_ptr() as usize),
+        kind,
+    }
+}
+
+/// Trim the whitespaces and the control codes at the end, the ascii ones are trimmed by bytes.
+fn trim_end(text: &str) -> &str {
+    let bytes = text.as_bytes();
+    let mut end = bytes.len();
+    while end > 0 && matches!(bytes[end - 1], 0..=b' ' | 0x7F) {
+        end -= 1;
+    }
+    match end > 0 && !bytes[end - 1].is_ascii() {
+        true => text[..end].trim_end_matches(|c: char| c.is_whitespace() || c.is_control()),
+        false => &text[..end],
+    }
+}
+
+/// The canonical screen, the newlines are `\n`, the control codes in the middle of line
+/// are mapped to spaces, the prompts are separated out.
+#[derive(Debug, Clone)]
+pub struct Screen<'a> {
+    /// the canonical text, it's borrowed when the original text is canonical already.
+    pub text: Cow<'a, str>,
+    /// the commands echoed, such as `AV:PKXSHA/03AUG`.
+    pub prompts: Vec<&'a str>,
+    /// the offset of every line in the canonical text and in the original text.
+    map: Vec<(usize, usize)>,
+}
+
+impl<'a> Screen<'a> {
+    /// Return the offset in the original text of the offset in the canonical text.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let i = self.map.partition_point(|(x, _)| *x <= offset);
+        match i.checked_sub(1).and_then(|i| self.map.get(i)) {
+            Some((canonical, original)) => original + (offset - canonical),
+            None => offset,
+        }
+    }
+
+    /// Return the range in the original text of the range in the canonical text.
+    pub fn original_range(&self, range: std::ops::Range<usize>) -> std::ops::Range<usize> {
+        let start = self.original_offset(range.start);
+        let end = match range.end {
+            end if end > range.start => self.original_offset(end - 1) + 1,
+            _ => start,
+        };
+        start..end
+    }
+}
+
+/// Normalize the text into the canonical screen.
+///
+/// # Examples
+///
+/// ```
+/// let text = ">RT KE9SWE\r\n 1.ZHANGSAN KE9SWE      \r\n 2.\x1DKMG123";
+/// let screen = eterm_parser::normalize::normalize(text);
+/// assert_eq!(screen.text, " 1.ZHANGSAN KE9SWE\n 2. KMG123");
+/// assert_eq!(screen.prompts, vec!["RT KE9SWE"]);
+/// let start = screen.text.find("KMG123").unwrap();
+/// assert_eq!(&text[screen.original_range(start..start + 6)], "KMG123");
+/// ```
+pub fn normalize(text: &str) -> Screen<'_> {
+    let mut canonical = String::with_capacity(text.len());
+    let mut prompts = Vec::new();
+    let mut map = Vec::new();
+    for line in lines(text) {
+        if line.is_prompt() {
+            prompts.push(line.text);
+            continue;
+        }
+        if !map.is_empty() {
+            canonical.push('\n');
+        }
+        map.push((canonical.len(), line.offset));
+        // the control codes are 1 byte as the space, so the offsets in the line are kept.
+        canonical.extend(line.text.chars().map(|c| match c.is_ascii_control() {
+            true => ' ',
+            false => c,
+        }));
+    }
+    let text = match canonical == text {
+        true => Cow::Borrowed(text),
+        false => Cow::Owned(canonical),
+    };
+    Screen { text, prompts, map }
+}
@@ -1,6 +1,7 @@
-use crate::util;
+use crate::render::Render;
+use crate::{error, normalize, util};
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct Pat<'a> {
     pub raw_text: &'a str,
     pub items: Option<Vec<PatItem<'a>>>,
@@ -13,42 +14,61 @@ impl<'a> Pat<'a> {
                 "pnr parameter shouldn't be empty.".to_owned(),
             ));
         }
-        let re = regex::Regex::new(
+        error::check(text)?;
+        let re = util::regex!(
             r"(?<INDEX>\d+)\s+(?<SEATCLASS>[/\w+]+)\s+FARE:(?<FARE>[\w.]+)\s+TAX:(?<TAX>[\w.]+)\s+YQ:(?<YQ>[\w.]+)\s+TOTAL:(?<TOTAL>[\w.]+)",
-        )?;
+        );
 
         let pat = Self {
             raw_text: text,
             items: Some(
-                re.captures_iter(text)
-                    .filter_map(|caps| {
-                        match (
-                            caps.name("INDEX"),
-                            caps.name("SEATCLASS"),
-                            caps.name("FARE"),
-                            caps.name("TAX"),
-                            caps.name("YQ"),
-                            caps.name("TOTAL"),
-                        ) {
-                            (index, seatclass, fare, tax, yq, total) => Some(PatItem {
-                                index: util::regex_extact_value::<u8>(index),
-                                seat_class: seatclass.and_then(|x| Some(x.as_str().trim())), // crate::regex_extact_text(seatclass),
-                                fare: fare.and_then(|x| PatPrice::parse(x.as_str()).ok()),
-                                tax: tax.and_then(|x| PatPrice::parse(x.as_str()).ok()),
-                                yq: yq.and_then(|x| PatPrice::parse(x.as_str()).ok()),
-                                total: util::regex_extact_value::<f32>(total),
-                                raw_text: caps.get(0).and_then(|x| Some(x.as_str())),
-                            }),
-                        }
+                normalize::screen_lines(text)
+                    .flat_map(|line| re.captures_iter(line))
+                    .map(|caps| PatItem {
+                        index: util::regex_extact_value::<u8>(caps.name("INDEX")),
+                        seat_class: util::regex_extact_text(caps.name("SEATCLASS")),
+                        fare: caps
+                            .name("FARE")
+                            .and_then(|x| PatPrice::parse(x.as_str()).ok()),
+                        tax: caps
+                            .name("TAX")
+                            .and_then(|x| PatPrice::parse(x.as_str()).ok()),
+                        yq: caps.name("YQ").and_then(|x| PatPrice::parse(x.as_str()).ok()),
+                        total: util::regex_extact_value::<f32>(caps.name("TOTAL")),
+                        raw_text: caps.get(0).map(|x| x.as_str()),
                     })
                     .collect::<Vec<_>>(),
             ),
         };
-        return Ok(pat);
+        Ok(pat)
     }
 }
 
-#[derive(Default, Debug)]
+impl Render for Pat<'_> {
+    /// Render the lines of price, such as
+    /// `01 T FARE:CNY520.00 TAX:CNY50.00 YQ:CNY110.00  TOTAL:680.00`.
+    fn render(&self) -> String {
+        let price = |x: &Option<PatPrice>| x.as_ref().map_or(String::new(), PatPrice::render);
+        self.items
+            .iter()
+            .flatten()
+            .map(|x| {
+                format!(
+                    "{:02} {} FARE:{} TAX:{} YQ:{}  TOTAL:{}",
+                    x.index.unwrap_or_default(),
+                    x.seat_class.unwrap_or_default(),
+                    price(&x.fare),
+                    price(&x.tax),
+                    price(&x.yq),
+                    x.total.map_or(String::new(), util::format_price),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[derive(Default, Debug, PartialEq)]
 pub struct PatItem<'a> {
     pub index: Option<u8>,
     pub seat_class: Option<&'a str>,
@@ -67,6 +87,18 @@ pub struct PatPrice<'a> {
 }
 
 impl<'a> PatPrice<'a> {
+    /// Render the price, such as `CNY520.00` and `TEXEMPT`.
+    fn render(&self) -> String {
+        match self.is_exemption {
+            true => "TEXEMPT".to_owned(),
+            false => format!(
+                "{}{}",
+                self.currency.unwrap_or_default(),
+                self.price.map_or(String::new(), util::format_price)
+            ),
+        }
+    }
+
     pub fn parse(text: &'a str) -> anyhow::Result<Self> {
         let mut pat_price = Self {
             ..Default::default()
@@ -76,8 +108,18 @@ impl<'a> PatPrice<'a> {
             pat_price.price = Some(0f32);
             pat_price.is_exemption = true;
         } else {
-            pat_price.currency = Some(&tx[0..3]);
-            pat_price.price = tx[3..].parse::<f32>().ok();
+            match (tx.get(0..3), tx.get(3..)) {
+                (Some(currency), Some(price)) => {
+                    pat_price.currency = Some(currency);
+                    pat_price.price = price.parse::<f32>().ok();
+                }
+                _ => {
+                    return Err(anyhow::Error::msg(format!(
+                        "pat price has no currency: {}",
+                        text
+                    )))
+                }
+            }
             pat_price.is_exemption = false;
         }
         Ok(pat_price)
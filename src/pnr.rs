@@ -1,7 +1,9 @@
-use crate::util;
+use crate::av::{day_of_week, MONTHS};
+use crate::render::Render;
+use crate::{error, normalize, util};
 
 /// The result that pnr text parsed.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct Pnr<'a> {
     pub infos: Option<Vec<&'a str>>,
     pub ssr_items: Option<Vec<SSR<'a>>>,
@@ -23,109 +25,59 @@ pub struct Pnr<'a> {
 
 impl<'a> Pnr<'a> {
     pub fn parse(text: &'a str) -> anyhow::Result<Self> {
+        Self::parse_with(text, RmkRegistry::builtin())
+    }
+
+    /// Parse pnr text with the remark codes that registered in the registry.
+    pub fn parse_with(text: &'a str, rmk_registry: &RmkRegistry) -> anyhow::Result<Self> {
         if text.is_empty() {
             return Err(anyhow::Error::msg(
                 "pnr parameter shouldn't be empty.".to_owned(),
             ));
         }
+        error::check(text)?;
         let mut pnr = Self {
             ..Default::default()
         };
 
-        let mut info_parsed = false;
-        let mut index = 0u8;
-        for line in text.lines() {
-            if !info_parsed && line.starts_with(" 1.") {
-                info_parsed = true;
-            }
-            if !info_parsed {
-                if line.contains("**ELECTRONIC TICKET PNR**") {
-                    pnr.is_ticket_pnr = Some(true);
-                }
-                if line.contains("*THIS PNR WAS ENTIRELY CANCELLED*") {
-                    pnr.is_cancelled_pnr = Some(true);
-                }
-                if line.contains("MARRIED SEGMENT EXIST IN THE PNR") {
-                    pnr.has_married_segment = Some(true);
-                }
-                pnr.infos.get_or_insert(Vec::new()).push(line);
-            } else {
-                match line.trim_start() {
-                    x if x.starts_with("1.") => {
-                        let re = regex::Regex::new(r"(?<NMS>1\.(.*))(?<PNRCODE>\w{6})\s*$")?;
-                        match re.captures(line) {
-                            Some(caps) => match (caps.name("NMS"), caps.name("PNRCODE")) {
-                                (Some(nms), Some(pnrcode)) => {
-                                    pnr.pnr_code = Some(pnrcode.as_str());
-                                    if let Ok(items) = NM::parse(index, nms.as_str()) {
-                                        pnr.nm_items = Some(items);
-                                    }
-                                }
-                                _ => {}
-                            },
-                            _ => {}
-                        }
-                    }
-                    x if regex::Regex::is_match(
-                        &regex::Regex::new(
-                            r"(?<GROUPPNRNAME>.*)\s*NM(?<PAXCOUNT>\d+)\s+(?<PNRCODE>\w{6})/(\w{2})",
-                        )?,
-                        x,
-                    ) =>
-                    {
-                        pnr.is_group_pnr = Some(true);
-                        if let Some(caps) = regex::Regex::captures(
-                            &regex::Regex::new(
-                                r"(?<GROUPPNRNAME>.*)\s*NM(?<PAXCOUNT>\d+)\s+(?<PNRCODE>\w{6})/(\w{2})",
-                            )?,
-                            x,
-                        ) {
-                            match (
-                                caps.name("GROUPPNRNAME"),
-                                caps.name("PAXCOUNT"),
-                                caps.name("PNRCODE"),
-                            ) {
-                                (Some(pnr_groupname), Some(paxcount), Some(pnrcode)) => {
-                                    pnr.group_pnr_name = Some(pnr_groupname.as_str());
-                                    pnr.pnr_code = Some(pnrcode.as_str());
-                                    pnr.pax_count = paxcount.as_str().parse::<u8>().ok();
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                    x if x.starts_with(&format!("{}. ", index)) => {
-                        if let Ok(item) = SEG::parse(index, line) {
-                            pnr.seg_items.get_or_insert(Vec::new()).push(item);
-                        }
+        for element in Self::elements_iter_with(text, rmk_registry) {
+            match element {
+                PnrElement::Info(line) => {
+                    if line.contains("**ELECTRONIC TICKET PNR**") {
+                        pnr.is_ticket_pnr = Some(true);
                     }
-                    x if x.starts_with(&format!("{}SSR", index)) => {
-                        if let Ok(item) = SSR::parse(index, line) {
-                            pnr.ssr_items.get_or_insert(Vec::new()).push(item);
-                        }
+                    if line.contains("*THIS PNR WAS ENTIRELY CANCELLED*") {
+                        pnr.is_cancelled_pnr = Some(true);
                     }
-                    x if x.starts_with(&format!("{}OSI", index)) => {
-                        if let Ok(item) = OSI::parse(index, line) {
-                            pnr.osi_items.get_or_insert(Vec::new()).push(item);
-                        }
+                    if line.contains("MARRIED SEGMENT EXIST IN THE PNR") {
+                        pnr.has_married_segment = Some(true);
                     }
-                    x if x.starts_with(&format!("{}RMK", index)) => {
-                        if let Ok(item) = RMK::parse(index, line) {
-                            pnr.rmk_items.get_or_insert(Vec::new()).push(item);
-                        }
+                    pnr.infos.get_or_insert(Vec::new()).push(line);
+                }
+                PnrElement::Names { pnr_code, items } => {
+                    pnr.pnr_code = Some(pnr_code);
+                    if items.is_some() {
+                        pnr.nm_items = items;
                     }
-                    _ => {
-                        if let Ok(item) = OtherItem::parse(index, line) {
-                            pnr.other_items.get_or_insert(Vec::new()).push(item);
-                        }
+                }
+                PnrElement::Group { name, pax_count, pnr_code } => {
+                    pnr.is_group_pnr = Some(true);
+                    if let (Some(name), Some(pnr_code)) = (name, pnr_code) {
+                        pnr.group_pnr_name = Some(name);
+                        pnr.pnr_code = Some(pnr_code);
+                        pnr.pax_count = pax_count;
                     }
                 }
+                PnrElement::Seg(item) => pnr.seg_items.get_or_insert(Vec::new()).push(item),
+                PnrElement::Ssr(item) => pnr.ssr_items.get_or_insert(Vec::new()).push(item),
+                PnrElement::Osi(item) => pnr.osi_items.get_or_insert(Vec::new()).push(item),
+                PnrElement::Rmk(item) => pnr.rmk_items.get_or_insert(Vec::new()).push(item),
+                PnrElement::Other(item) => pnr.other_items.get_or_insert(Vec::new()).push(item),
             }
-            index += 1;
         }
         Self::fix_nm(&mut pnr);
         if pnr.pax_count.is_none() {
-            pnr.pax_count = pnr.nm_items.as_ref().and_then(|x| Some(x.len() as u8));
+            pnr.pax_count = pnr.nm_items.as_ref().map(|x| x.len() as u8);
         }
         pnr.office_no = pnr.other_items.as_ref().and_then(|x| {
             x.iter().find_map(|n| {
@@ -139,7 +91,7 @@ impl<'a> Pnr<'a> {
         pnr.bpnr_code = pnr.rmk_items.as_ref().and_then(|x| {
             x.iter().find_map(|n| {
                 if n.service_code.is_some_and(|s| s == "CA") {
-                    n.text.as_ref().and_then(|k| Some(&k[0..6]))
+                    n.field("bpnr")
                 } else {
                     None
                 }
@@ -148,29 +100,279 @@ impl<'a> Pnr<'a> {
         Ok(pnr)
     }
 
+    /// Return the elements of the text, which are parsed one line at a time as they're asked for,
+    /// so that the rest of the text isn't parsed after the element needed is found.
+    /// The names aren't filled with the ids of the FOID SSR, which come after them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eterm_parser::pnr::{Pnr, PnrElement};
+    ///
+    /// let text = r" 1.ZHANGSAN KE9SWE
+    ///  2.  MU5101 Y   SU27AUG  PEKSHA HK1   0900 1110          E T2T2
+    ///  3.KMG123";
+    /// let seg = Pnr::elements_iter(text).find_map(|x| match x {
+    ///     PnrElement::Seg(seg) => Some(seg),
+    ///     _ => None,
+    /// });
+    /// assert_eq!(seg.unwrap().flight_no, Some("MU5101"));
+    /// ```
+    pub fn elements_iter(text: &'a str) -> PnrElements<'a, 'static> {
+        Self::elements_iter_with(text, RmkRegistry::builtin())
+    }
+
+    /// Return the elements of the text with the remark codes that registered in the registry.
+    pub fn elements_iter_with<'r>(
+        text: &'a str,
+        rmk_registry: &'r RmkRegistry,
+    ) -> PnrElements<'a, 'r> {
+        PnrElements {
+            lines: normalize::lines(text),
+            rmk_registry,
+            info_parsed: false,
+            index: 0,
+        }
+    }
+
+    /// Return the number of the element that the line starts with, such as 11 of "11.OSI".
+    fn element_number(line: &str) -> Option<u8> {
+        let (number, _) = line.trim_start().split_once('.')?;
+        number.parse::<u8>().ok()
+    }
+
+    /// Return the ticket numbers of the pnr, which come from SSR TKNE and TN items.
+    pub fn ticket_numbers(&self) -> Vec<&'a str> {
+        let tkne = self.ssr_items.iter().flatten().filter_map(|x| {
+            if x.service_code != Some("TKNE") {
+                return None;
+            }
+            x.text?
+                .split([' ', '/'])
+                .find(|n| n.len() == 13 && n.bytes().all(|b| b.is_ascii_digit()))
+        });
+        let tn = self.other_items.iter().flatten().filter_map(|x| {
+            if x.item_type != "TN" {
+                return None;
+            }
+            x.raw
+                .split_once("TN/")
+                .and_then(|(_, n)| n.split('/').next())
+                .map(|n| n.trim())
+        });
+        let mut numbers = Vec::new();
+        for n in tkne.chain(tn) {
+            if !numbers.contains(&n) {
+                numbers.push(n);
+            }
+        }
+        numbers
+    }
+
     /// fill id info with ssr.
     fn fix_nm(pnr: &mut Pnr) {
-        match (&pnr.ssr_items, &mut pnr.nm_items) {
-            (Some(ssrs), Some(nms)) => {
-                nms.iter_mut().for_each(|x| {
-                    if let Some(ssr) = ssrs.iter().find(|s| {
-                        s.passenger_index.is_some_and(|n| n == x.index)
-                            && s.service_code.is_some_and(|n| n == "FOID")
-                    }) {
-                        if let Some(tx) = &ssr.text {
-                            x.id_type = Some(&tx[0..2]);
-                            x.id_number = Some(&tx[2..]);
+        if let (Some(ssrs), Some(nms)) = (&pnr.ssr_items, &mut pnr.nm_items) {
+            nms.iter_mut().for_each(|x| {
+                if let Some(ssr) = ssrs.iter().find(|s| {
+                    s.passenger_index.is_some_and(|n| n == x.index)
+                        && s.service_code.is_some_and(|n| n == "FOID")
+                }) {
+                    if let Some(tx) = &ssr.text {
+                        if let (Some(id_type), Some(id_number)) = (tx.get(0..2), tx.get(2..)) {
+                            x.id_type = Some(id_type);
+                            x.id_number = Some(id_number);
                         }
                     }
-                });
+                }
+            });
+        }
+    }
+}
+
+impl Render for Pnr<'_> {
+    /// Render the infos, the names with the pnr code and the elements in the order of number,
+    /// the element that isn't parsed is rendered as its raw text.
+    fn render(&self) -> String {
+        let mut lines = self
+            .infos
+            .iter()
+            .flatten()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        let Some(pnr_code) = self.pnr_code else {
+            return lines.join("\n");
+        };
+        let mut names = String::from(" 1.");
+        for (i, nm) in self.nm_items.iter().flatten().enumerate() {
+            if i > 0 {
+                names.push_str(&format!(" {}.", i + 1));
+            }
+            names.push_str(&format!("{} ", nm.name.unwrap_or(nm.raw)));
+        }
+        lines.push(format!("{}{}", names, pnr_code));
+        if let (Some(true), Some(name)) = (self.is_group_pnr, self.group_pnr_name) {
+            let airline = self
+                .seg_items
+                .iter()
+                .flatten()
+                .find_map(|x| {
+                    x.flight_no
+                        .and_then(|x| x.trim_start_matches('*').get(0..2))
+                })
+                .unwrap_or("YY");
+            lines.push(format!(
+                "{}NM{} {}/{}",
+                name,
+                self.pax_count.unwrap_or_default(),
+                pnr_code,
+                airline
+            ));
+        }
+        let mut elements = Vec::new();
+        elements.extend(
+            self.seg_items
+                .iter()
+                .flatten()
+                .map(|x| (x.index, x.raw, x.render())),
+        );
+        elements.extend(
+            self.ssr_items
+                .iter()
+                .flatten()
+                .map(|x| (x.index, x.raw, x.render())),
+        );
+        elements.extend(
+            self.osi_items
+                .iter()
+                .flatten()
+                .map(|x| (x.index, x.raw, x.render())),
+        );
+        elements.extend(
+            self.rmk_items
+                .iter()
+                .flatten()
+                .map(|x| (x.index, x.raw, x.render())),
+        );
+        elements.extend(
+            self.other_items
+                .iter()
+                .flatten()
+                .map(|x| (x.index, x.raw, Some(x.raw.to_owned()))),
+        );
+        // the line without number follows the element before it, so it comes first.
+        elements.sort_by_key(|(index, raw, _)| (*index, Pnr::element_number(raw).is_some()));
+        lines.extend(
+            elements
+                .into_iter()
+                .map(|(_, raw, line)| line.unwrap_or_else(|| raw.to_owned())),
+        );
+        lines.join("\n")
+    }
+}
+
+/// The element of pnr, see [`Pnr::elements_iter`].
+#[derive(Debug)]
+pub enum PnrElement<'a> {
+    /// the line before the elements, such as `**ELECTRONIC TICKET PNR**`.
+    Info(&'a str),
+    /// the names of the line such as ` 1.ZHANGSAN 2.LISI KE9SWE`, with the pnr code.
+    Names {
+        pnr_code: &'a str,
+        items: Option<Vec<NM<'a>>>,
+    },
+    /// the name of group pnr, such as `GROUPNAME NM10 KE9SWE/CA`.
+    Group {
+        name: Option<&'a str>,
+        pax_count: Option<u8>,
+        pnr_code: Option<&'a str>,
+    },
+    Seg(SEG<'a>),
+    Ssr(SSR<'a>),
+    Osi(OSI<'a>),
+    Rmk(RMK<'a>),
+    Other(OtherItem<'a>),
+}
+
+/// The iterator of the elements of pnr, see [`Pnr::elements_iter`].
+#[derive(Debug, Clone)]
+pub struct PnrElements<'a, 'r> {
+    lines: normalize::Lines<'a>,
+    rmk_registry: &'r RmkRegistry,
+    /// the lines before the first element are the infos.
+    info_parsed: bool,
+    /// the number of the element, the lines without number follow the previous one.
+    index: u8,
+}
+
+impl<'a, 'r> PnrElements<'a, 'r> {
+    /// Parse the line of element, None when it's invalid.
+    fn parse(&self, line: &'a str) -> Option<PnrElement<'a>> {
+        let index = self.index;
+        let text = line.trim_start();
+        if text.starts_with("1.") {
+            let re = util::regex!(r"(?<NMS>1\.(.*))(?<PNRCODE>\w{6})\s*$");
+            let caps = re.captures(line)?;
+            let (nms, pnrcode) = (caps.name("NMS")?, caps.name("PNRCODE")?);
+            return Some(PnrElement::Names {
+                pnr_code: pnrcode.as_str(),
+                items: NM::parse(index, nms.as_str()).ok(),
+            });
+        }
+        let re =
+            util::regex!(r"(?<GROUPPNRNAME>.*)\s*NM(?<PAXCOUNT>\d+)\s+(?<PNRCODE>\w{6})/(\w{2})");
+        if let Some(caps) = re.captures(text) {
+            return Some(PnrElement::Group {
+                name: caps.name("GROUPPNRNAME").map(|x| x.as_str()),
+                pax_count: caps
+                    .name("PAXCOUNT")
+                    .and_then(|x| x.as_str().parse::<u8>().ok()),
+                pnr_code: caps.name("PNRCODE").map(|x| x.as_str()),
+            });
+        }
+        match text {
+            x if x.starts_with(&format!("{}. ", index)) => {
+                SEG::parse(index, line).ok().map(PnrElement::Seg)
+            }
+            x if x.starts_with(&format!("{}.SSR", index)) => {
+                SSR::parse(index, line).ok().map(PnrElement::Ssr)
+            }
+            x if x.starts_with(&format!("{}.OSI", index)) => {
+                OSI::parse(index, line).ok().map(PnrElement::Osi)
+            }
+            x if x.starts_with(&format!("{}.RMK", index)) => {
+                RMK::parse_with(index, line, self.rmk_registry).ok().map(PnrElement::Rmk)
+            }
+            _ => OtherItem::parse(index, line).ok().map(PnrElement::Other),
+        }
+    }
+}
+
+impl<'a, 'r> Iterator for PnrElements<'a, 'r> {
+    type Item = PnrElement<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = self.lines.by_ref().find(|x| !x.is_prompt())?.text;
+            if !self.info_parsed && line.starts_with(" 1.") {
+                self.info_parsed = true;
+            }
+            if let Some(number) = Pnr::element_number(line) {
+                self.index = number;
+            }
+            let element = match self.info_parsed {
+                true => self.parse(line),
+                false => Some(PnrElement::Info(line)),
+            };
+            self.index = self.index.wrapping_add(1);
+            if element.is_some() {
+                return element;
             }
-            _ => {}
         }
     }
 }
 
 /// This is a simple item, except NM,SSR,OSI,SEG,RMK, etc.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct OtherItem<'a> {
     pub index: u8,
     pub item_type: &'a str,
@@ -179,30 +381,30 @@ pub struct OtherItem<'a> {
 
 impl<'a> OtherItem<'a> {
     pub fn parse(index: u8, raw: &'a str) -> anyhow::Result<Self> {
-        let item_type = match raw {
-            x if x.starts_with(&format!("{}TL", index)) => "TL",
-            x if x.starts_with(&format!("{}FN", index)) => "FN",
-            x if x.starts_with(&format!("{}FC", index)) => "FC",
-            x if x.starts_with(&format!("{}FP", index)) => "FP",
-            x if x.starts_with(&format!("{}EI", index)) => "EI",
-            x if x.starts_with(&format!("{}XN", index)) => "XN",
-            x if x.starts_with(&format!("{}TC", index)) => "TC",
-            x if x.starts_with(&format!("{}TN/", index)) => "TN",
-            x if regex::Regex::is_match(&regex::Regex::new(r"^\d{2}\.[A-Z]{3}\d{3}$")?, x) => {
+        let item_type = match raw.trim_start() {
+            x if x.starts_with(&format!("{}.TL", index)) => "TL",
+            x if x.starts_with(&format!("{}.FN", index)) => "FN",
+            x if x.starts_with(&format!("{}.FC", index)) => "FC",
+            x if x.starts_with(&format!("{}.FP", index)) => "FP",
+            x if x.starts_with(&format!("{}.EI", index)) => "EI",
+            x if x.starts_with(&format!("{}.XN", index)) => "XN",
+            x if x.starts_with(&format!("{}.TC", index)) => "TC",
+            x if x.starts_with(&format!("{}.TN/", index)) => "TN",
+            x if regex::Regex::is_match(util::regex!(r"^\d{2}\.[A-Z]{3}\d{3}$"), x) => {
                 "OFFICE"
             }
             _ => "TEXT",
         };
         Ok(Self {
             index,
-            item_type: item_type,
-            raw: raw,
+            item_type,
+            raw,
         })
     }
 }
 
 /// The passenger infomation of pnr.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct NM<'a> {
     pub index: u8,
     pub raw: &'a str,
@@ -215,12 +417,12 @@ pub struct NM<'a> {
 
 impl<'a> NM<'a> {
     pub fn parse(index: u8, raw: &'a str) -> anyhow::Result<Vec<Self>> {
-        let re = regex::Regex::new(r"(\d+\.)")?;
+        let re = util::regex!(r"(\d+\.)");
         let nms = re
             .split(raw)
             .filter_map(|cap| match cap.trim() {
-                x if x.is_empty() => None,
-                x if x.ends_with(".") => None,
+                "" => None,
+                x if x.ends_with('.') => None,
                 x => Some(Self {
                     index,
                     raw: cap.trim(),
@@ -234,7 +436,7 @@ impl<'a> NM<'a> {
 }
 
 /// The flight segment infomation of pnr.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct SEG<'a> {
     pub index: u8,
     pub raw: &'a str,
@@ -253,9 +455,9 @@ pub struct SEG<'a> {
 
 impl<'a> SEG<'a> {
     pub fn parse(index: u8, raw: &'a str) -> anyhow::Result<Self> {
-        let re = regex::Regex::new(
+        let re = util::regex!(
             r"(?<FLIGHTNO>\*?\w{5,6})\s+(?<SEATCLASS>[A-Z]\d?)\s+[A-Z]{2}(?<FLIGHTDATE>\d{2}[A-Z]{3}(?:\d{2})?)\s*(?<ORG>[A-Z]{3})(?<DST>[A-Z]{3})\s*(?<ACTIONCODE>[A-Z]{2})(?<ACTIONCODEQTY>\d{1,2})\s*(?<DEPTIME>\d{4})\s*(?<ARRTIME>\d{4})(?:\+(?<ADDDAY>\d))?",
-        )?;
+        );
         match re.captures(raw) {
             Some(caps) => match (
                 caps.name("FLIGHTNO"),
@@ -282,7 +484,7 @@ impl<'a> SEG<'a> {
                     addday,
                 ) => Ok(Self {
                     index,
-                    raw: raw,
+                    raw,
                     flight_no: Some(flight_no.as_str()),
                     seat_class: Some(seat_class.as_str()),
                     flight_date: Some(flight_date.as_str()),
@@ -297,21 +499,75 @@ impl<'a> SEG<'a> {
                 }),
                 _ => Ok(Self {
                     index,
-                    raw: raw,
+                    raw,
                     ..Default::default()
                 }),
             },
             _ => Ok(Self {
                 index,
-                raw: raw,
+                raw,
                 ..Default::default()
             }),
         }
     }
 }
 
+impl SEG<'_> {
+    /// Render the line of segment, such as
+    /// ` 2.  MU5101 Y   SU27AUG  PEKSHA HK1   0900 1110          E`.
+    fn render(&self) -> Option<String> {
+        let flight_date = self.flight_date?;
+        let mut line = format!(
+            "{:>2}.  {} {}   {}{}  {}{} {}{}   {} {}",
+            self.index,
+            self.flight_no?,
+            self.seat_class?,
+            self.weekday(flight_date),
+            flight_date,
+            self.org?,
+            self.dst?,
+            self.action_code?,
+            self.action_code_qty?,
+            self.takeoff?,
+            self.landing?,
+        );
+        if let Some(x) = self.landing_addday {
+            line.push_str(&format!("+{}", x));
+        }
+        line.push_str("          E");
+        Some(line)
+    }
+
+    /// Return the weekday of the date such as `SU`, which is the one of the raw text
+    /// or the one computed from the date with year, such as `27AUG23`.
+    fn weekday(&self, flight_date: &str) -> String {
+        if let Some(x) = self
+            .raw
+            .find(flight_date)
+            .and_then(|i| self.raw.get(i.saturating_sub(2)..i))
+            .filter(|x| x.len() == 2 && x.bytes().all(|b| b.is_ascii_uppercase()))
+        {
+            return x.to_owned();
+        }
+        let date = (
+            flight_date.get(0..2).and_then(|x| x.parse::<u32>().ok()),
+            flight_date
+                .get(2..5)
+                .and_then(|x| MONTHS.iter().position(|m| *m == x)),
+            flight_date.get(5..7).and_then(|x| x.parse::<i32>().ok()),
+        );
+        match date {
+            (Some(day), Some(month), Some(year)) => {
+                let n = day_of_week(2000 + year, month as u32 + 1, day);
+                ["SU", "MO", "TU", "WE", "TH", "FR", "SA"][n].to_owned()
+            }
+            _ => "XX".to_owned(),
+        }
+    }
+}
+
 /// The ssr infomation of pnr.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct SSR<'a> {
     pub index: u8,
     pub raw: &'a str,
@@ -326,9 +582,9 @@ pub struct SSR<'a> {
 
 impl<'a> SSR<'a> {
     pub fn parse(index: u8, raw: &'a str) -> anyhow::Result<Self> {
-        let re = regex::Regex::new(
-            r"SSR (?<SERVICECODE>[A-Z]+) (?<AIRLINE>\w{2}) (?:(?<ACTIONCODE>\w{2})(?<ACTIONCODEQTY>\d|/+)?\s+)?(?<TEXT>[^\r\n]*(?<!/P\d+)(?<!/S\d+))(/P(?<PASSENGERINDEX>\d+))?(/S(?<SEGMENTINDEX>\d+))?\s*$",
-        )?;
+        let re = util::regex!(
+            r"SSR (?<SERVICECODE>[A-Z]+) (?<AIRLINE>\w{2}) (?:(?<ACTIONCODE>\w{2})(?<ACTIONCODEQTY>\d|/+)?\s+)?(?<TEXT>[^\r\n]*?)(/P(?<PASSENGERINDEX>\d+))?(/S(?<SEGMENTINDEX>\d+))?\s*$",
+        );
         match re.captures(raw) {
             Some(caps) => match (
                 caps.name("SERVICECODE"),
@@ -349,7 +605,7 @@ impl<'a> SSR<'a> {
                     segment_index,
                 ) => Ok(Self {
                     index,
-                    raw: raw,
+                    raw,
                     service_code: Some(service_code.as_str()),
                     airline: Some(airline.as_str()),
                     action_code: action_code.map(|x| x.as_str()),
@@ -360,21 +616,46 @@ impl<'a> SSR<'a> {
                 }),
                 _ => Ok(Self {
                     index,
-                    raw: raw,
+                    raw,
                     ..Default::default()
                 }),
             },
             _ => Ok(Self {
                 index,
-                raw: raw,
+                raw,
                 ..Default::default()
             }),
         }
     }
 }
 
+impl SSR<'_> {
+    /// Render the line of ssr, such as ` 4.SSR FOID CA HK1 NI110101199001011234/P1`.
+    fn render(&self) -> Option<String> {
+        let mut line = format!(
+            "{:>2}.SSR {} {} ",
+            self.index, self.service_code?, self.airline?
+        );
+        if let Some(x) = self.action_code {
+            line.push_str(x);
+            if let Some(x) = self.action_code_qty {
+                line.push_str(&x.to_string());
+            }
+            line.push(' ');
+        }
+        line.push_str(self.text?);
+        if let Some(x) = self.passenger_index {
+            line.push_str(&format!("/P{}", x));
+        }
+        if let Some(x) = self.segment_index {
+            line.push_str(&format!("/S{}", x));
+        }
+        Some(line)
+    }
+}
+
 /// The osi infomation of pnr.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct OSI<'a> {
     pub index: u8,
     pub raw: &'a str,
@@ -386,9 +667,9 @@ pub struct OSI<'a> {
 
 impl<'a> OSI<'a> {
     pub fn parse(index: u8, raw: &'a str) -> anyhow::Result<Self> {
-        let re = regex::Regex::new(
-            r"OSI (?<AIRLINE>\w{2}) (?<SERVICECODE>[A-Z]+)?(?<TEXT>(.*(?<!/P\d+)))(/P(?<PASSENGERINDEX>\d+))?$",
-        )?;
+        let re = util::regex!(
+            r"OSI (?<AIRLINE>\w{2}) (?<SERVICECODE>[A-Z]+)?(?<TEXT>.*?)(/P(?<PASSENGERINDEX>\d+))?\s*$",
+        );
         match re.captures(raw) {
             Some(caps) => match (
                 caps.name("AIRLINE"),
@@ -398,7 +679,7 @@ impl<'a> OSI<'a> {
             ) {
                 (Some(airline), Some(service_code), Some(text), passenger_index) => Ok(Self {
                     index,
-                    raw: raw,
+                    raw,
                     service_code: Some(service_code.as_str()),
                     airline: Some(airline.as_str()),
                     text: Some(text.as_str()),
@@ -406,58 +687,352 @@ impl<'a> OSI<'a> {
                 }),
                 _ => Ok(Self {
                     index,
-                    raw: raw,
+                    raw,
                     ..Default::default()
                 }),
             },
             _ => Ok(Self {
                 index,
-                raw: raw,
+                raw,
                 ..Default::default()
             }),
         }
     }
 }
 
+impl OSI<'_> {
+    /// Render the line of osi, such as ` 5.OSI CA CTCT13800000000/P1`.
+    fn render(&self) -> Option<String> {
+        let mut line = format!(
+            "{:>2}.OSI {} {}{}",
+            self.index, self.airline?, self.service_code?, self.text?
+        );
+        if let Some(x) = self.passenger_index {
+            line.push_str(&format!("/P{}", x));
+        }
+        Some(line)
+    }
+}
+
 /// The remark infomation of pnr.
-#[derive(Default, Debug)]
+#[derive(Default, Debug, PartialEq)]
 pub struct RMK<'a> {
     pub index: u8,
     pub raw: &'a str,
     pub service_code: Option<&'a str>,
     pub text: Option<&'a str>,
     pub passenger_index: Option<u8>,
+    /// the named fields that the decoder of the service code produced.
+    pub fields: Option<Vec<(&'static str, &'a str)>>,
 }
 
 impl<'a> RMK<'a> {
     pub fn parse(index: u8, raw: &'a str) -> anyhow::Result<Self> {
-        let re = regex::Regex::new(
-            r"RMK[ :/](?<SERVICECODE>(MP|TJ AUTH|CA|CID|TID|EMAIL|1A|GMJC|RV|ORI))[ :/](?<TEXT>.*?)(/P(?<PASSENGERINDEX>\d))?$",
-        )?;
-        match re.captures(raw) {
-            Some(caps) => match (
-                caps.name("SERVICECODE"),
-                caps.name("TEXT"),
-                caps.name("PASSENGERINDEX"),
-            ) {
-                (Some(service_code), text, passenger_index) => Ok(Self {
-                    index,
-                    raw: raw,
-                    service_code: Some(service_code.as_str()),
-                    text: util::regex_extact_text(text),
-                    passenger_index: util::regex_extact_value::<u8>(passenger_index),
-                }),
-                _ => Ok(Self {
-                    index,
-                    raw: raw,
-                    ..Default::default()
-                }),
-            },
-            _ => Ok(Self {
-                index,
-                raw: raw,
-                ..Default::default()
-            }),
+        Self::parse_with(index, raw, RmkRegistry::builtin())
+    }
+
+    /// Parse the remark with the service codes that registered in the registry.
+    pub fn parse_with(index: u8, raw: &'a str, registry: &RmkRegistry) -> anyhow::Result<Self> {
+        let mut rmk = Self {
+            index,
+            raw,
+            ..Default::default()
+        };
+        let body = match raw.split_once("RMK") {
+            Some((_, body)) if body.starts_with([' ', ':', '/']) => &body[1..],
+            _ => return Ok(rmk),
+        };
+        let body = body.trim_end();
+        let body = match body.rsplit_once("/P") {
+            Some((text, pax)) if !pax.is_empty() && pax.bytes().all(|b| b.is_ascii_digit()) => {
+                rmk.passenger_index = pax.parse::<u8>().ok();
+                text
+            }
+            _ => body,
+        };
+        match registry.find(body) {
+            Some((code, text)) => {
+                rmk.service_code = Some(&body[..code.code.len()]);
+                rmk.text = Some(text.trim());
+                rmk.fields = code.decoder.map(|decode| decode(text.trim()));
+            }
+            None => rmk.text = Some(body.trim()),
+        }
+        Ok(rmk)
+    }
+
+    /// Return the value of the field that decoded from the text.
+    pub fn field(&self, name: &str) -> Option<&'a str> {
+        self.fields
+            .as_ref()?
+            .iter()
+            .find_map(|(k, v)| if *k == name { Some(*v) } else { None })
+    }
+}
+
+impl RMK<'_> {
+    /// Render the line of remark, such as ` 6.RMK CA/NZJ0JY`.
+    fn render(&self) -> Option<String> {
+        let text = self.text?;
+        let mut line = format!("{:>2}.RMK ", self.index);
+        match self.service_code {
+            Some(code) if text.is_empty() => line.push_str(code),
+            Some(code) => line.push_str(&format!("{}/{}", code, text)),
+            None => line.push_str(text),
+        }
+        if let Some(x) = self.passenger_index {
+            line.push_str(&format!("/P{}", x));
+        }
+        Some(line)
+    }
+}
+
+/// The decoder that splits the text of a remark into named fields.
+pub type RmkDecoder = for<'a> fn(&'a str) -> Vec<(&'static str, &'a str)>;
+
+/// The service code of remark that registered in a `RmkRegistry`.
+#[derive(Debug, Clone)]
+pub struct RmkCode {
+    pub code: String,
+    pub decoder: Option<RmkDecoder>,
+}
+
+/// The registry of the service codes of remark.
+///
+/// The default registry contains the built-in codes, such as MP,TJ AUTH,CA,CID,TID,EMAIL,1A,GMJC,RV,ORI.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::pnr::{RmkRegistry, RMK};
+///
+/// let mut registry = RmkRegistry::default();
+/// registry.register("ORDERNO", Some(|text| vec![("order_no", text)]));
+/// let rmk = RMK::parse_with(5, " 5.RMK ORDERNO/20230905001", &registry).unwrap();
+/// assert_eq!(rmk.service_code, Some("ORDERNO"));
+/// assert_eq!(rmk.field("order_no"), Some("20230905001"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RmkRegistry {
+    codes: Vec<RmkCode>,
+}
+
+impl Default for RmkRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        for code in [
+            "MP", "TJ AUTH", "CID", "TID", "EMAIL", "1A", "GMJC", "RV", "ORI",
+        ] {
+            registry.register(code, None);
+        }
+        registry.register("CA", Some(decode_ca));
+        registry
+    }
+}
+
+impl RmkRegistry {
+    /// Return a registry without any code.
+    pub fn empty() -> Self {
+        Self { codes: Vec::new() }
+    }
+
+    /// Return the shared registry that contains the built-in codes.
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: std::sync::OnceLock<RmkRegistry> = std::sync::OnceLock::new();
+        BUILTIN.get_or_init(Self::default)
+    }
+
+    /// Register a service code with its decoder, the decoder of the same code will be replaced.
+    pub fn register(&mut self, code: &str, decoder: Option<RmkDecoder>) -> &mut Self {
+        let code = code.trim().to_uppercase();
+        self.codes.retain(|x| x.code != code);
+        self.codes.push(RmkCode { code, decoder });
+        // the longer code first, so TJ AUTH wins against TJ.
+        self.codes.sort_by_key(|x| std::cmp::Reverse(x.code.len()));
+        self
+    }
+
+    /// Remove a service code from the registry.
+    pub fn unregister(&mut self, code: &str) -> &mut Self {
+        let code = code.trim().to_uppercase();
+        self.codes.retain(|x| x.code != code);
+        self
+    }
+
+    /// Return the code registered.
+    pub fn get(&self, code: &str) -> Option<&RmkCode> {
+        let code = code.trim().to_uppercase();
+        self.codes.iter().find(|x| x.code == code)
+    }
+
+    /// Find the code that the body of remark starts with, return the code and the rest text.
+    fn find<'t>(&self, body: &'t str) -> Option<(&RmkCode, &'t str)> {
+        self.codes.iter().find_map(|x| {
+            let rest = body.strip_prefix(x.code.as_str())?;
+            if rest.is_empty() {
+                Some((x, rest))
+            } else if rest.starts_with([' ', ':', '/']) {
+                Some((x, &rest[1..]))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Decode the remark of CA, such as `RMK CA/NZJ0JY`, the first 6 chars are the big pnr.
+fn decode_ca(text: &str) -> Vec<(&'static str, &str)> {
+    match text.get(0..6) {
+        Some(bpnr) => vec![("bpnr", bpnr)],
+        None => Vec::new(),
+    }
+}
+
+/// The change between two snapshots of the same pnr.
+///
+/// The elements are matched by their content rather than by element number,
+/// because eterm renumbers every element after an edit.
+#[derive(Debug, PartialEq)]
+pub enum PnrChange<'a> {
+    NameAdded(&'a NM<'a>),
+    NameRemoved(&'a NM<'a>),
+    /// the passenger at the same position has a different name.
+    NameChanged {
+        old: &'a NM<'a>,
+        new: &'a NM<'a>,
+    },
+    SegmentAdded(&'a SEG<'a>),
+    SegmentRemoved(&'a SEG<'a>),
+    /// the time or the flight number of a segment has changed.
+    ScheduleChanged {
+        old: &'a SEG<'a>,
+        new: &'a SEG<'a>,
+    },
+    /// the action code of a segment has changed, such as HK to UN or TK.
+    StatusChanged {
+        old: &'a SEG<'a>,
+        new: &'a SEG<'a>,
+    },
+    SsrAdded(&'a SSR<'a>),
+    SsrRemoved(&'a SSR<'a>),
+    TicketAdded(&'a str),
+    TicketRemoved(&'a str),
+}
+
+/// Compare two snapshots of the same pnr and return the changes from `old` to `new`.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::pnr::{self, PnrChange};
+///
+/// let old = eterm_parser::parse_pnr(r" 1.ZHANGSAN KE9SWE
+///  2.  JD5324 Y   WE06SEP  DXJPKX HK1   1045 1310          E
+///  3.KMG123").unwrap();
+/// let new = eterm_parser::parse_pnr(r" 1.ZHANGSAN KE9SWE
+///  2.  JD5324 Y   WE06SEP  DXJPKX UN1   1045 1310          E
+///  3.  JD5326 Y   WE06SEP  DXJPKX TK1   1145 1410          E
+///  4.KMG123").unwrap();
+/// let changes = pnr::diff(&old, &new);
+/// assert!(matches!(changes[0], PnrChange::StatusChanged { .. }));
+/// assert!(matches!(changes[1], PnrChange::SegmentAdded(_)));
+/// ```
+pub fn diff<'a>(old: &'a Pnr<'a>, new: &'a Pnr<'a>) -> Vec<PnrChange<'a>> {
+    let mut changes = Vec::new();
+    diff_nm(old, new, &mut changes);
+    diff_seg(old, new, &mut changes);
+    diff_ssr(old, new, &mut changes);
+    diff_ticket(old, new, &mut changes);
+    changes
+}
+
+/// Split both sides into the pairs that `same` matches and the rest of each side.
+fn pair_items<'a, T>(
+    olds: &[&'a T],
+    news: &[&'a T],
+    same: impl Fn(&T, &T) -> bool,
+) -> (Vec<(&'a T, &'a T)>, Vec<&'a T>, Vec<&'a T>) {
+    let mut pairs = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = news.to_vec();
+    for o in olds {
+        match added.iter().position(|n| same(o, n)) {
+            Some(i) => pairs.push((*o, added.remove(i))),
+            None => removed.push(*o),
         }
     }
+    (pairs, removed, added)
+}
+
+fn items<T>(items: &Option<Vec<T>>) -> Vec<&T> {
+    items.iter().flatten().collect()
+}
+
+fn diff_nm<'a>(old: &'a Pnr<'a>, new: &'a Pnr<'a>, changes: &mut Vec<PnrChange<'a>>) {
+    let (_, removed, added) = pair_items(&items(&old.nm_items), &items(&new.nm_items), |o, n| {
+        o.name == n.name
+    });
+    let mut added = added.into_iter();
+    for o in removed {
+        match added.next() {
+            Some(n) => changes.push(PnrChange::NameChanged { old: o, new: n }),
+            None => changes.push(PnrChange::NameRemoved(o)),
+        }
+    }
+    changes.extend(added.map(PnrChange::NameAdded));
+}
+
+fn diff_seg<'a>(old: &'a Pnr<'a>, new: &'a Pnr<'a>, changes: &mut Vec<PnrChange<'a>>) {
+    fn flight_no<'s>(x: &SEG<'s>) -> Option<&'s str> {
+        x.flight_no.map(|f| f.trim_start_matches('*'))
+    }
+    let same_route =
+        |o: &SEG, n: &SEG| o.org == n.org && o.dst == n.dst && o.flight_date == n.flight_date;
+    let (pairs, removed, added) =
+        pair_items(&items(&old.seg_items), &items(&new.seg_items), |o, n| {
+            same_route(o, n) && flight_no(o) == flight_no(n)
+        });
+    // a segment whose flight number changed on the same route and date is a schedule change.
+    let (moved, removed, added) = pair_items(&removed, &added, same_route);
+    for (o, n) in pairs.into_iter().chain(moved) {
+        if flight_no(o) != flight_no(n)
+            || o.takeoff != n.takeoff
+            || o.landing != n.landing
+            || o.landing_addday != n.landing_addday
+        {
+            changes.push(PnrChange::ScheduleChanged { old: o, new: n });
+        }
+        if o.action_code != n.action_code {
+            changes.push(PnrChange::StatusChanged { old: o, new: n });
+        }
+    }
+    changes.extend(removed.into_iter().map(PnrChange::SegmentRemoved));
+    changes.extend(added.into_iter().map(PnrChange::SegmentAdded));
+}
+
+fn diff_ssr<'a>(old: &'a Pnr<'a>, new: &'a Pnr<'a>, changes: &mut Vec<PnrChange<'a>>) {
+    let (_, removed, added) = pair_items(&items(&old.ssr_items), &items(&new.ssr_items), |o, n| {
+        o.service_code == n.service_code
+            && o.airline == n.airline
+            && o.action_code == n.action_code
+            && o.text == n.text
+            && o.passenger_index == n.passenger_index
+    });
+    changes.extend(removed.into_iter().map(PnrChange::SsrRemoved));
+    changes.extend(added.into_iter().map(PnrChange::SsrAdded));
+}
+
+fn diff_ticket<'a>(old: &'a Pnr<'a>, new: &'a Pnr<'a>, changes: &mut Vec<PnrChange<'a>>) {
+    let olds = old.ticket_numbers();
+    let news = new.ticket_numbers();
+    changes.extend(
+        olds.iter()
+            .filter(|x| !news.contains(x))
+            .map(|x| PnrChange::TicketRemoved(x)),
+    );
+    changes.extend(
+        news.iter()
+            .filter(|x| !olds.contains(x))
+            .map(|x| PnrChange::TicketAdded(x)),
+    );
 }
@@ -0,0 +1,136 @@
+use crate::{lexer, normalize, util};
+
+/// The profile of the columns of av and ml, the columns shift by a position or two
+/// between the offices and the versions of eterm.
+///
+/// The columns are moved by the offset of the profile, except the first column
+/// such as the option number of av, which is widened instead.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::profile::Profile;
+///
+/// let text = "1- *KN6856   DS# JA C8 YA BA HA KA LA RQ SQ TQ  PKXXIY 0900   1120   321 0^B  E  ";
+/// assert_eq!(Profile::detect(text).name, "shift+1");
+/// assert_eq!(Profile::by_name("standard"), Some(&Profile::STANDARD));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Profile {
+    pub name: &'static str,
+    /// the positions that the columns of av are moved by.
+    pub av_offset: isize,
+    /// the positions that the columns of ml are moved by.
+    pub ml_offset: isize,
+}
+
+/// The profiles that are detected from the text.
+const BUILTIN: [Profile; 4] = [
+    Profile::STANDARD,
+    Profile::new("shift+1", 1, 1),
+    Profile::new("shift+2", 2, 2),
+    Profile::new("shift-1", -1, -1),
+];
+
+/// The status of flight that is at the column 12 of the line of flight in the standard profile.
+const AV_STATUS_ANCHORS: [&str; 4] = ["DS#", "AS#", "DS!", "AS!"];
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl Profile {
+    /// The profile that the parsers are written for.
+    pub const STANDARD: Self = Self::new("standard", 0, 0);
+
+    /// Create a profile, so that the columns of other eterm versions can be parsed.
+    pub const fn new(name: &'static str, av_offset: isize, ml_offset: isize) -> Self {
+        Self {
+            name,
+            av_offset,
+            ml_offset,
+        }
+    }
+
+    /// Return the builtin profiles.
+    pub fn builtin() -> &'static [Profile] {
+        &BUILTIN
+    }
+
+    /// Return the builtin profile of the name.
+    pub fn by_name(name: &str) -> Option<&'static Profile> {
+        BUILTIN.iter().find(|x| x.name == name)
+    }
+
+    /// Detect the builtin profile by probing the anchors, such as `DS#` of the line of flight
+    /// and the `>` line of av, the group count of the line of passenger of ml.
+    /// It's the standard profile when no anchor is found.
+    pub fn detect(text: &str) -> &'static Profile {
+        let find =
+            |f: fn(&Profile) -> isize, offset: isize| BUILTIN.iter().find(|x| f(x) == offset);
+        if let Some(profile) = Self::probe_av(text).and_then(|x| find(|p| p.av_offset, x)) {
+            return profile;
+        }
+        if let Some(profile) = Self::probe_ml(text).and_then(|x| find(|p| p.ml_offset, x)) {
+            return profile;
+        }
+        &BUILTIN[0]
+    }
+
+    /// Detect the builtin profile from the status of a line of flight of av, such as `DS#`.
+    pub(crate) fn detect_av_line(line: &str) -> Option<&'static Profile> {
+        let offset = Self::probe_av_status(line)?;
+        BUILTIN.iter().find(|x| x.av_offset == offset)
+    }
+
+    /// Return the offset of av from the status of flight, or the flight number of the `>` line,
+    /// such as `>   MU2104` which flight number is at the column 4.
+    fn probe_av(text: &str) -> Option<isize> {
+        if let Some(offset) = normalize::screen_lines(text).find_map(Self::probe_av_status) {
+            return Some(offset);
+        }
+        normalize::screen_lines(text).find_map(|line| {
+            let rest = line.strip_prefix('>')?;
+            let no = rest.trim_start_matches(' ');
+            // the line of cabins only, such as `>               GQ UQ ZQ`, isn't an anchor.
+            if no.len() == rest.len() || !lexer::is_flight_no(no) {
+                return None;
+            }
+            Some((line.len() - no.len()) as isize - 4)
+        })
+    }
+
+    /// Return the offset of av from the status of flight, which is at the column 12.
+    fn probe_av_status(line: &str) -> Option<isize> {
+        // the anchors all end with `#` or `!`, so the first anchor is found in a single scan.
+        line.bytes()
+            .enumerate()
+            .filter(|(_, b)| matches!(b, b'#' | b'!'))
+            .find_map(|(i, _)| {
+                let pos = i.checked_sub(2)?;
+                AV_STATUS_ANCHORS
+                    .contains(&line.get(pos..=i)?)
+                    .then_some(pos)
+            })
+            .map(|pos| util::width(&line[..pos]) as isize - 12)
+    }
+
+    /// Return the offset of ml from the group count of the line of passenger,
+    /// such as ` 001   0DILIAYIAILI` which group count is at the column 7.
+    fn probe_ml(text: &str) -> Option<isize> {
+        normalize::screen_lines(text).find_map(|line| {
+            let index = line.trim_start_matches(' ');
+            let rest = index.trim_start_matches(|c: char| c.is_ascii_digit());
+            if index.len() - rest.len() != 3 {
+                return None;
+            }
+            let group = rest.trim_start_matches(' ');
+            if !group.starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+            Some((line.len() - group.len()) as isize - 7)
+        })
+    }
+}
@@ -0,0 +1,17 @@
+/// The result that renders the screen text back, in the layout that its parser accepts,
+/// so that the parser returns the same result from the text.
+///
+/// The raw texts such as `raw_text` aren't rendered, they're the text that is rendered.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::render::Render;
+///
+/// let text = "MULTI\n8L9681 /08SEP          C\nURCKHG\n 001   0DILIAYIAILI      HP3M9L T HX1  VVV211 07SEP      K    T";
+/// let ml = eterm_parser::parse_ml(text).unwrap();
+/// assert_eq!(ml.render(), text);
+/// ```
+pub trait Render {
+    fn render(&self) -> String;
+}
@@ -0,0 +1,130 @@
+use crate::lexer::{self, AvKind};
+use crate::{av, detr, error, fd, ml, normalize, pat, pnr};
+
+/// The kind of response of screen, which is the command that the screen responds to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ResponseKind {
+    Av,
+    Detr,
+    Fd,
+    Ml,
+    Pat,
+    /// the response of rt command.
+    Pnr,
+}
+
+impl ResponseKind {
+    /// The kinds of response that are parsed.
+    pub const ALL: [Self; 6] = [
+        Self::Av,
+        Self::Detr,
+        Self::Fd,
+        Self::Ml,
+        Self::Pat,
+        Self::Pnr,
+    ];
+
+    /// Detect the kind of response by the command echoed such as `>AV:PKXSHA/03AUG`,
+    /// or by the anchors of the screen when there is no command echoed,
+    /// such as `FD:` of fd and `MULTI` of ml.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use eterm_parser::response::ResponseKind;
+    ///
+    /// assert_eq!(ResponseKind::detect(">RT KE9SWE\n 1.ZHANGSAN KE9SWE"), Some(ResponseKind::Pnr));
+    /// assert_eq!(ResponseKind::detect("MULTI\n8L9681 /08SEP"), Some(ResponseKind::Ml));
+    /// assert_eq!(ResponseKind::detect("NO DISPLAY"), None);
+    /// ```
+    pub fn detect(text: &str) -> Option<Self> {
+        let prompt = normalize::lines(text).find(|x| x.is_prompt());
+        if let Some(kind) = prompt.and_then(|x| Self::of_command(x.text)) {
+            return Some(kind);
+        }
+        let mut lines = normalize::screen_lines(text).filter(|x| !x.trim().is_empty());
+        if let Some("MULTI" | "NO-OP") = lines.next().map(|x| x.trim()) {
+            return Some(Self::Ml);
+        }
+        let any = |f: fn(&str) -> bool| normalize::screen_lines(text).any(f);
+        if any(|x| x.starts_with("FD:")) {
+            Some(Self::Fd)
+        } else if any(|x| x.contains("ISSUED BY:")) {
+            Some(Self::Detr)
+        } else if any(|x| x.contains("FARE:") && x.contains("TOTAL:")) {
+            Some(Self::Pat)
+        } else if lexer::av(text).any(|(_, x)| matches!(x, AvKind::Header(_) | AvKind::Option(_))) {
+            Some(Self::Av)
+        } else if any(|x| {
+            x.trim_start().starts_with("1.") || x.contains("**ELECTRONIC TICKET PNR**")
+        }) {
+            Some(Self::Pnr)
+        } else {
+            None
+        }
+    }
+
+    /// Return the kind of response of the command, such as `AV:PKXSHA/03AUG` and `RT KE9SWE`.
+    fn of_command(command: &str) -> Option<Self> {
+        let name = command.trim_start();
+        let name = &name[..name
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(name.len())];
+        match name.to_ascii_uppercase().as_str() {
+            "AV" | "AVH" => Some(Self::Av),
+            "DETR" => Some(Self::Detr),
+            "FD" => Some(Self::Fd),
+            "ML" => Some(Self::Ml),
+            "PAT" => Some(Self::Pat),
+            "RT" => Some(Self::Pnr),
+            _ => None,
+        }
+    }
+
+    /// Parse the text as the kind of response.
+    pub fn parse(self, text: &str) -> anyhow::Result<Response<'_>> {
+        Ok(match self {
+            Self::Av => Response::Av(crate::parse_av(text)?),
+            Self::Detr => Response::Detr(crate::parse_detr(text)?),
+            Self::Fd => Response::Fd(crate::parse_fd(text)?),
+            Self::Ml => Response::Ml(crate::parse_ml(text)?),
+            Self::Pat => Response::Pat(crate::parse_pat(text)?),
+            Self::Pnr => Response::Pnr(crate::parse_pnr(text)?),
+        })
+    }
+}
+
+/// The result that a screen parsed, which kind of response is detected from the screen.
+#[derive(Debug)]
+pub enum Response<'a> {
+    Av(av::Av<'a>),
+    Detr(detr::Detr<'a>),
+    Fd(fd::Fd<'a>),
+    Ml(ml::Ml<'a>),
+    Pat(pat::Pat<'a>),
+    Pnr(pnr::Pnr<'a>),
+}
+
+impl<'a> Response<'a> {
+    /// Parse the text with the kind of response detected from the text.
+    pub fn parse(text: &'a str) -> anyhow::Result<Self> {
+        error::check(text)?;
+        match ResponseKind::detect(text) {
+            Some(kind) => kind.parse(text),
+            None => Err(anyhow::Error::msg(
+                "the kind of response of the screen isn't detected.".to_owned(),
+            )),
+        }
+    }
+
+    pub fn kind(&self) -> ResponseKind {
+        match self {
+            Self::Av(_) => ResponseKind::Av,
+            Self::Detr(_) => ResponseKind::Detr,
+            Self::Fd(_) => ResponseKind::Fd,
+            Self::Ml(_) => ResponseKind::Ml,
+            Self::Pat(_) => ResponseKind::Pat,
+            Self::Pnr(_) => ResponseKind::Pnr,
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::error;
+use crate::stream::{Screen, ScreenReader};
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// The policy of reconnecting when the connection is lost, see [`Session::reconnect`].
+///
+/// The command is sent again after reconnecting only when nothing was sent, that the connection
+/// isn't made, see [`ConnectError`], or it's lost before the command is written, see [`NotSentError`].
+/// Otherwise the host may have done it already, such as the connection is reset after it's written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// the times of reconnecting for a command, 0 is never.
+    pub attempts: u32,
+    /// the delay before reconnecting.
+    pub delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            delay: Duration::from_millis(500),
+        }
+    }
+}
+
+/// The error that the connection to eterm server isn't made, nothing was sent before it,
+/// so the command is sent again after reconnecting even though it timed out.
+#[derive(Debug)]
+pub struct ConnectError {
+    pub addr: String,
+    pub source: io::Error,
+}
+
+impl std::fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "can't connect to eterm server {}: {}",
+            self.addr, self.source
+        )
+    }
+}
+
+impl std::error::Error for ConnectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// The error that the command isn't sent, the connection is lost before the `\r` that ends it is written,
+/// so the command is sent again after reconnecting.
+#[derive(Debug)]
+pub struct NotSentError {
+    pub command: String,
+    pub source: io::Error,
+}
+
+impl std::fmt::Display for NotSentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "can't send {} to eterm server: {}",
+            self.command, self.source
+        )
+    }
+}
+
+impl std::error::Error for NotSentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// The connection of session, the screens are read from the clone of the stream.
+#[derive(Debug)]
+struct Connection {
+    stream: TcpStream,
+    screens: ScreenReader<TcpStream>,
+}
+
+impl Connection {
+    /// Send the command and return the screen replied.
+    fn exchange(&mut self, command: &str) -> anyhow::Result<Screen> {
+        self.write(command)?;
+        Ok(self.read(command)?)
+    }
+
+    fn write(&mut self, command: &str) -> Result<(), NotSentError> {
+        self.stream
+            .write_all(format!("{}\r", command).as_bytes())
+            .map_err(|source| NotSentError {
+                command: command.to_owned(),
+                source,
+            })
+    }
+
+    /// Return the screen replied to the command.
+    fn read(&mut self, command: &str) -> io::Result<Screen> {
+        match self.screens.next() {
+            Some(Ok(screen)) => Ok(screen),
+            Some(Err(e)) if matches!(e.kind(), io::ErrorKind::WouldBlock) => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("eterm server didn't reply to {}", command),
+            )),
+            Some(Err(e)) => Err(e),
+            None => Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "eterm server closed the connection",
+            )),
+        }
+    }
+
+    /// Send the command and return the screen of its pages, which are asked for with `PN`.
+    /// The `PN` that isn't sent is an error of the command, which is done already.
+    fn exchange_pages(&mut self, command: &str, max_pages: usize) -> anyhow::Result<Screen> {
+        let mut screen = self.exchange(command)?;
+        let mut pages = 1;
+        while screen.has_more && pages < max_pages {
+            self.write("PN").map_err(|e| e.source)?;
+            let page = self.read("PN")?;
+            screen.text.push_str("\r\n");
+            screen.text.push_str(&page.text);
+            screen.has_more = page.has_more;
+            pages += 1;
+        }
+        Ok(screen)
+    }
+}
+
+/// The session of eterm terminal over TCP, which signs in when it connects,
+/// and collects the pages of the response with `PN`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use eterm_parser::response::Response;
+/// use eterm_parser::session::Session;
+///
+/// let mut session = Session::new("127.0.0.1:350");
+/// session.sign_in("SI:1234/PASSWORD");
+/// let screen = session.send("AV:PKXSHA/03AUG").unwrap();
+/// if let Ok(Response::Av(av)) = screen.parse() {
+///     assert_eq!(av.dpt, Some("PKX"));
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Session {
+    addr: String,
+    sign_in: Option<String>,
+    connect_timeout: Duration,
+    timeout: Duration,
+    max_pages: usize,
+    reconnect: ReconnectPolicy,
+    connection: Option<Connection>,
+}
+
+impl Session {
+    /// Create the session of the address such as `127.0.0.1:350`, it connects on the first command.
+    pub fn new(addr: &str) -> Self {
+        Self {
+            addr: addr.to_owned(),
+            sign_in: None,
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(30),
+            max_pages: 20,
+            reconnect: ReconnectPolicy::default(),
+            connection: None,
+        }
+    }
+
+    /// Set the command of sign-in that is sent when it connects, such as `SI:1234/PASSWORD`.
+    pub fn sign_in(&mut self, command: &str) -> &mut Self {
+        self.sign_in = Some(command.to_owned());
+        self
+    }
+
+    pub fn connect_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set the time that a screen is waited for.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the most pages of a response, the pages after them aren't asked for.
+    pub fn max_pages(&mut self, max_pages: usize) -> &mut Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    pub fn reconnect(&mut self, policy: ReconnectPolicy) -> &mut Self {
+        self.reconnect = policy;
+        self
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Connect and sign in, return the screen of sign-in,
+    /// or the [`HostError`](crate::error::HostError) that the sign-in is refused with.
+    pub fn connect(&mut self) -> anyhow::Result<Option<Screen>> {
+        self.connection = None;
+        let mut last_error = None;
+        for addr in self.addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.connect_timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    let screens = ScreenReader::new(stream.try_clone()?);
+                    let mut connection = Connection { stream, screens };
+                    let screen = match &self.sign_in {
+                        Some(command) => Some(connection.exchange(command)?),
+                        None => None,
+                    };
+                    // the sign-in is refused with the error such as `AUTHORITY`.
+                    if let Some(screen) = &screen {
+                        error::check(&screen.text)?;
+                    }
+                    self.connection = Some(connection);
+                    return Ok(screen);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(match last_error {
+            Some(source) => ConnectError {
+                addr: self.addr.clone(),
+                source,
+            }
+            .into(),
+            None => anyhow::Error::msg(format!("address not found: {}", self.addr)),
+        })
+    }
+
+    /// Disconnect, the next command connects again.
+    pub fn close(&mut self) {
+        self.connection = None;
+    }
+
+    /// Send the command and return the screen of the whole response,
+    /// the pages after the first are asked for with `PN` and appended to it.
+    pub fn send(&mut self, command: &str) -> anyhow::Result<Screen> {
+        let mut attempts = 0;
+        loop {
+            match self.try_send(command) {
+                Err(e) if attempts < self.reconnect.attempts && is_unsent(&e) => {
+                    attempts += 1;
+                    self.connection = None;
+                    std::thread::sleep(self.reconnect.delay);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn try_send(&mut self, command: &str) -> anyhow::Result<Screen> {
+        if self.connection.is_none() {
+            self.connect()?;
+        }
+        let Some(connection) = self.connection.as_mut() else {
+            return Err(anyhow::Error::msg(
+                "eterm session isn't connected.".to_owned(),
+            ));
+        };
+        let result = connection.exchange_pages(command, self.max_pages);
+        if result.is_err() {
+            self.connection = None;
+        }
+        result
+    }
+}
+
+/// Return whether the error is that nothing was sent, the connection isn't made
+/// or it's lost before the command is written.
+fn is_unsent(e: &anyhow::Error) -> bool {
+    e.is::<ConnectError>() || e.is::<NotSentError>()
+}
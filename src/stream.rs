@@ -0,0 +1,257 @@
+use crate::normalize;
+use crate::response::{Response, ResponseKind};
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+/// The byte that eterm server ends a response with.
+const ETX: u8 = 0x03;
+
+/// The marks of the start of entry in bytes, a screen starts at them, see [`normalize`].
+const SOE: [&[u8]; 2] = [b"\x1E", "►".as_bytes()];
+
+/// The marks of the start of entry in GBK, which hasn't `►` but the `●` of 0xA1 0xF1 for it,
+/// and the `►` in GB18030. They're marks only at the start of a character, the bytes
+/// may be the trail byte of a character and the lead byte of the next one.
+const GBK_SOE: [&[u8]; 2] = [b"\xA1\xF1", b"\x81\x37\x9C\x38"];
+
+/// The size of the bytes that the readers read at a time.
+const READ_SIZE: usize = 4096;
+
+/// The screen that is assembled from the chunks of bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screen {
+    pub text: String,
+    /// the last line ends with `+`, the response continues on the next page that `PN` shows.
+    pub has_more: bool,
+}
+
+impl Screen {
+    fn new(bytes: &[u8]) -> Option<Self> {
+        // the mark of GBK is `►` as the screens of UTF-8, so that the command is recognised.
+        let text = match GBK_SOE.iter().find(|x| bytes.starts_with(x)) {
+            Some(soe) => format!("►{}", decode(&bytes[soe.len()..])),
+            None => decode(bytes),
+        };
+        if text.trim().is_empty() {
+            return None;
+        }
+        let has_more = normalize::screen_lines(&text)
+            .filter(|x| !x.trim().is_empty())
+            .last()
+            .is_some_and(|x| x.ends_with('+'));
+        Some(Self { text, has_more })
+    }
+
+    /// Return the kind of response of the screen, see [`ResponseKind::detect`].
+    pub fn kind(&self) -> Option<ResponseKind> {
+        ResponseKind::detect(&self.text)
+    }
+
+    /// Parse the screen with the parser of the kind of response detected.
+    pub fn parse(&self) -> anyhow::Result<Response<'_>> {
+        Response::parse(&self.text)
+    }
+}
+
+#[cfg(feature = "encoding")]
+fn decode(bytes: &[u8]) -> String {
+    let mut buf = String::new();
+    crate::encoding::decode(bytes, &mut buf).0.to_owned()
+}
+
+#[cfg(not(feature = "encoding"))]
+fn decode(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// The assembler of the screens from the chunks of bytes that arrive from the socket,
+/// only the bytes of the screen that is arriving are kept.
+///
+/// A screen ends at the `\x03` that ends a response, or at the SOE such as `►`
+/// that the next command is echoed after, in UTF-8 or GBK.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::stream::ScreenAssembler;
+///
+/// let mut assembler = ScreenAssembler::new();
+/// assert!(assembler.push(">RT KE9SWE\r\n 1.ZHANGSAN KE9".as_bytes()).is_empty());
+/// let screens = assembler.push(" KE9SWE\r\n 2.KMG123\x03\u{25BA}>PN".as_bytes());
+/// assert_eq!(screens.len(), 1);
+/// assert_eq!(screens[0].text, ">RT KE9SWE\r\n 1.ZHANGSAN KE9 KE9SWE\r\n 2.KMG123");
+/// assert_eq!(assembler.finish().unwrap().text, "►>PN");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ScreenAssembler {
+    buf: Vec<u8>,
+    /// the bytes that are scanned for the boundaries of screen.
+    scanned: usize,
+    /// the byte that is scanned next is the trail byte of a double bytes character of GBK.
+    trail: bool,
+}
+
+impl ScreenAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push a chunk of bytes, return the screens that are completed by it.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<Screen> {
+        self.buf.extend_from_slice(chunk);
+        let mut screens = Vec::new();
+        let mut start = 0;
+        // the `►` is up to 4 bytes that may be split between the chunks, the last 3 bytes
+        // are scanned again with the next chunk.
+        let resume = self.buf.len().saturating_sub(3);
+        let mut trail = self.trail;
+        let mut resume_trail = trail;
+        for i in self.scanned..self.buf.len() {
+            if i == resume {
+                resume_trail = trail;
+            }
+            let rest = &self.buf[i..];
+            if rest[0] == ETX {
+                screens.extend(Screen::new(&self.buf[start..i]));
+                start = i + 1;
+                trail = false;
+                continue;
+            }
+            if i > start
+                && (SOE.iter().any(|x| rest.starts_with(x))
+                    || !trail && GBK_SOE.iter().any(|x| rest.starts_with(x)))
+            {
+                screens.extend(Screen::new(&self.buf[start..i]));
+                start = i;
+            }
+            trail = !trail && (0x81..=0xFE).contains(&rest[0]);
+        }
+        self.buf.drain(..start);
+        (self.scanned, self.trail) = match resume.checked_sub(start) {
+            Some(resume) => (resume, resume_trail),
+            None => (0, false),
+        };
+        screens
+    }
+
+    /// Return the screen of the bytes left, at the end of the stream.
+    pub fn finish(&mut self) -> Option<Screen> {
+        self.scanned = 0;
+        self.trail = false;
+        Screen::new(&std::mem::take(&mut self.buf))
+    }
+}
+
+/// The screens that are read but not returned yet, which the readers share.
+#[derive(Debug)]
+struct Pending {
+    assembler: ScreenAssembler,
+    screens: VecDeque<Screen>,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl Pending {
+    fn new() -> Self {
+        Self {
+            assembler: ScreenAssembler::new(),
+            screens: VecDeque::new(),
+            buf: vec![0; READ_SIZE],
+            done: false,
+        }
+    }
+
+    /// Assemble the bytes that are read into the buffer, none is read at the end of the stream.
+    fn feed(&mut self, n: usize) {
+        match n {
+            0 => {
+                self.done = true;
+                self.screens.extend(self.assembler.finish());
+            }
+            n => self.screens.extend(self.assembler.push(&self.buf[..n])),
+        }
+    }
+}
+
+/// The iterator of the screens that are read from the reader, such as a `TcpStream`.
+///
+/// # Examples
+///
+/// ```
+/// use eterm_parser::stream::ScreenReader;
+///
+/// let bytes = "MULTI\r\n8L9681 /08SEP\x03►>AV:PKXSHA/03AUG".as_bytes();
+/// let screens = ScreenReader::new(bytes).collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(screens.len(), 2);
+/// assert!(screens[0].parse().is_ok());
+/// ```
+#[derive(Debug)]
+pub struct ScreenReader<R> {
+    reader: R,
+    pending: Pending,
+}
+
+impl<R: Read> ScreenReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: Pending::new(),
+        }
+    }
+}
+
+impl<R: Read> Iterator for ScreenReader<R> {
+    type Item = io::Result<Screen>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(screen) = self.pending.screens.pop_front() {
+                return Some(Ok(screen));
+            }
+            if self.pending.done {
+                return None;
+            }
+            match self.reader.read(&mut self.pending.buf) {
+                Ok(n) => self.pending.feed(n),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// The reader of the screens from the async reader of tokio, see [`ScreenReader`].
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct AsyncScreenReader<R> {
+    reader: R,
+    pending: Pending,
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncRead + Unpin> AsyncScreenReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: Pending::new(),
+        }
+    }
+
+    /// Return the next screen, None at the end of the stream.
+    pub async fn next_screen(&mut self) -> io::Result<Option<Screen>> {
+        use tokio::io::AsyncReadExt;
+        loop {
+            if let Some(screen) = self.pending.screens.pop_front() {
+                return Ok(Some(screen));
+            }
+            if self.pending.done {
+                return Ok(None);
+            }
+            match self.reader.read(&mut self.pending.buf).await {
+                Ok(n) => self.pending.feed(n),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
@@ -1,13 +1,25 @@
+/// Return the regex of the pattern, which is compiled once at the first call,
+/// such as `util::regex!(r"^\d+")`.
+macro_rules! regex {
+    ($pattern:literal $(,)?) => {{
+        static REGEX: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+        fn compile() -> regex::Regex {
+            regex::Regex::new($pattern).expect("invalid regex")
+        }
+        REGEX.get_or_init(compile)
+    }};
+}
+pub(crate) use regex;
+
 #[inline]
-pub fn regex_extact<'a>(pattern: &str, text: &'a str) -> anyhow::Result<Option<&'a str>> {
-    let re = regex::Regex::new(pattern)?;
-    re.captures(&text).map_or(Ok(None), |caps| {
-        caps.get(1)
-            .map_or(Ok(None), |x| Ok(Some(x.as_str().trim())))
-    })
+pub fn regex_extact<'a>(re: &regex::Regex, text: &'a str) -> Option<&'a str> {
+    re.captures(text)
+        .and_then(|caps| caps.get(1))
+        .map(|x| x.as_str().trim())
 }
 
 #[inline]
+#[allow(clippy::bind_instead_of_map)]
 pub fn regex_extact_text(cap: Option<regex::Match<'_>>) -> Option<&str> {
     cap.and_then(|x| Some(x.as_str().trim()))
     //cap.map_or(None, |x| Some(x.as_str().trim().to_owned()))
@@ -16,4 +28,86 @@ pub fn regex_extact_text(cap: Option<regex::Match<'_>>) -> Option<&str> {
 #[inline]
 pub fn regex_extact_value<F: std::str::FromStr>(cap: Option<regex::Match<'_>>) -> Option<F> {
     cap.and_then(|x| x.as_str().parse::<F>().ok())
-}
\ No newline at end of file
+}
+
+/// Slice the text by bytes, the range is clamped into the text and moved to char boundaries,
+/// so a short or malformed line never panics.
+pub fn slice(text: &str, start: usize, end: usize) -> &str {
+    let floor = |mut n: usize| {
+        n = n.min(text.len());
+        while !text.is_char_boundary(n) {
+            n -= 1;
+        }
+        n
+    };
+    let (start, end) = (floor(start), floor(end));
+    if start < end {
+        &text[start..end]
+    } else {
+        ""
+    }
+}
+
+/// Return the columns that the char takes on the terminal, the east asian wide chars such as
+/// the chinese take 2 columns.
+pub fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x20000..=0x2FFFD
+        | 0x30000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Return the columns that the text takes on the terminal.
+pub fn width(text: &str) -> usize {
+    if text.is_ascii() {
+        return text.len();
+    }
+    text.chars().map(char_width).sum()
+}
+
+/// Slice the text by the columns on the terminal, a char belongs to the column that it starts at,
+/// the range beyond the text is cut off.
+pub fn slice_columns(text: &str, start: usize, end: usize) -> &str {
+    if text.is_ascii() {
+        return slice(text, start, end);
+    }
+    let (mut from, mut to) = (text.len(), text.len());
+    let mut column = 0;
+    for (i, c) in text.char_indices() {
+        if column >= start && from == text.len() {
+            from = i;
+        }
+        if column >= end {
+            to = i;
+            break;
+        }
+        column += char_width(c);
+    }
+    if from < to {
+        &text[from..to]
+    } else {
+        ""
+    }
+}
+
+/// Format the price with 2 decimals as eterm shows, such as `520.00`,
+/// the price that 2 decimals can't hold is formatted in full, so it parses back the same.
+pub fn format_price(price: f32) -> String {
+    let text = format!("{:.2}", price);
+    match text.parse::<f32>() == Ok(price) {
+        true => text,
+        false => price.to_string(),
+    }
+}